@@ -0,0 +1,56 @@
+use std::{
+    fs,
+    time::Instant
+};
+
+use crate::common::*;
+
+/// Periodic saving of the edited world to a sidecar file, next to the
+/// world file, or in the temporary directory for untitled worlds
+pub struct Autosave {
+    period:Duration,
+    last:Instant
+}
+
+impl Autosave {
+    pub fn new(period:Duration)->Self {
+	Self { period,last:Instant::now() }
+    }
+
+    pub fn path_for(path:Option<&Path>)->PathBuf {
+	match path {
+	    Some(path) => {
+		let mut u = path.as_os_str().to_owned();
+		u.push(".autosave");
+		PathBuf::from(u)
+	    },
+	    None => std::env::temp_dir().join("mzg-edit-untitled.autosave")
+	}
+    }
+
+    /// Is it time to autosave again?
+    pub fn due(&mut self)->bool {
+	if self.last.elapsed() >= self.period {
+	    self.last = Instant::now();
+	    true
+	} else {
+	    false
+	}
+    }
+
+    /// The autosave of a world, if there is one more recent than the
+    /// world file itself
+    pub fn recoverable(path:Option<&Path>)->Option<PathBuf> {
+	let mtime = |p:&Path| fs::metadata(p).and_then(|md| md.modified()).ok();
+	let autosave = Self::path_for(path);
+	let t_autosave = mtime(&autosave)?;
+	match path.and_then(mtime) {
+	    Some(t_world) if t_world >= t_autosave => None,
+	    _ => Some(autosave)
+	}
+    }
+
+    pub fn remove(path:Option<&Path>) {
+	let _ = fs::remove_file(Self::path_for(path));
+    }
+}
@@ -24,9 +24,12 @@ pub use eframe::{
 	Button,
 	CentralPanel,
 	Color32,
+	ComboBox,
 	Context,
+	DragValue,
 	Event,
 	EventFilter,
+	FontId,
 	Grid,
 	ImageSource,
 	Layout,
@@ -51,12 +54,14 @@ pub use eframe::{
 	scroll_area::ScrollBarVisibility,
 	Sense,
 	Stroke,
+	TextEdit,
 	TextureHandle,
 	TextureOptions,
 	Ui,
 	vec2,
 	Vec2,
 	ViewportBuilder,
+	ViewportCommand,
 	Widget,
 	Window,
     },
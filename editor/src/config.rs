@@ -0,0 +1,80 @@
+use std::fs;
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::common::*;
+
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct Recent {
+    pub path:PathBuf,
+    /// Room being edited when the world was last closed
+    pub room:Option<usize>
+}
+
+/// Per-user editor settings, kept in `$XDG_CONFIG_HOME/mzg-edit/config.ron`
+/// (or `~/.config/mzg-edit/config.ron`)
+#[derive(Clone,Debug,Default,Serialize,Deserialize)]
+pub struct Config {
+    /// Recently opened worlds, most recent first
+    pub recent:Vec<Recent>
+}
+
+impl Config {
+    const MAX_RECENT : usize = 10;
+
+    fn path()->Option<PathBuf> {
+	let dir = std::env::var_os("XDG_CONFIG_HOME")
+	    .map(PathBuf::from)
+	    .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+	Some(dir.join("mzg-edit").join("config.ron"))
+    }
+
+    /// Load the configuration, falling back to the defaults if there is
+    /// none or it is unreadable
+    pub fn load()->Self {
+	Self::path()
+	    .and_then(|path| fs::read_to_string(path).ok())
+	    .and_then(|u| ron::de::from_str(&u).ok())
+	    .unwrap_or_default()
+    }
+
+    pub fn save(&self)->Result<()> {
+	if let Some(path) = Self::path() {
+	    if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	    }
+	    fs::write(path,ron::ser::to_string_pretty(self,Default::default())?)?;
+	}
+	Ok(())
+    }
+
+    fn normalize(path:&Path)->PathBuf {
+	fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    /// Put a world at the top of the recent list
+    pub fn opened(&mut self,path:&Path) {
+	let path = Self::normalize(path);
+	let room =
+	    match self.recent.iter().position(|r| r.path == path) {
+		Some(i) => self.recent.remove(i).room,
+		None => None
+	    };
+	self.recent.insert(0,Recent { path,room });
+	self.recent.truncate(Self::MAX_RECENT);
+    }
+
+    pub fn set_room(&mut self,path:&Path,room:usize) {
+	let path = Self::normalize(path);
+	if let Some(r) = self.recent.iter_mut().find(|r| r.path == path) {
+	    r.room = Some(room);
+	}
+    }
+
+    pub fn last(&self)->Option<&Recent> {
+	self.recent.first()
+    }
+}
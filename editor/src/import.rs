@@ -0,0 +1,81 @@
+use std::ffi::OsString;
+
+use anyhow::{
+    anyhow,
+    bail
+};
+
+use crate::{
+    common::*,
+    sketch::{
+	self,
+	Palette
+    },
+    world::World
+};
+
+fn is_image(path:&Path)->bool {
+    path.extension()
+	.map(|e| e.eq_ignore_ascii_case("png"))
+	.unwrap_or(false)
+}
+
+/// Palette to use for an image sketch when none is given: a `.pal` file
+/// next to it
+pub fn default_palette(sketch:&Path)->Option<PathBuf> {
+    let palette = sketch.with_extension("pal");
+    if is_image(sketch) && palette.exists() {
+	Some(palette)
+    } else {
+	None
+    }
+}
+
+pub fn needs_palette(sketch:&Path)->bool {
+    is_image(sketch) && default_palette(sketch).is_none()
+}
+
+/// Add a room drawn in a text or PNG sketch to the world, under the next
+/// free id, which is returned
+pub fn import_room(world:&mut World,sketch:&Path,palette:Option<&Path>)->Result<usize> {
+    let rows =
+	if is_image(sketch) {
+	    let palette = palette.map(Path::to_path_buf)
+		.or_else(|| default_palette(sketch))
+		.ok_or_else(|| anyhow!("A palette is needed to import {:?}",sketch))?;
+	    let palette = Palette::load(&palette)?;
+	    let img = image::open(sketch)?.to_rgb8();
+	    sketch::image_rows(img.height() as usize,img.width() as usize,
+			       |iy,ix| img.get_pixel(ix as u32,iy as u32).0,
+			       &palette)?
+	} else {
+	    sketch::text_rows(&std::fs::read_to_string(sketch)?)
+	};
+    let id = world.last_id().map(|id| id + 1).unwrap_or(0);
+    let name = sketch.file_stem()
+	.map(|s| s.to_string_lossy().to_string())
+	.unwrap_or_else(|| format!("Room {}",id));
+    let (room,start) = sketch::room_from_rows(id,&name,&rows,&world.tile_types)?;
+    world.insert_room(room);
+    world.start = world.start.or(start);
+    Ok(id)
+}
+
+/// Command line importer:
+/// `mzg-editor --import WORLD SKETCH [PALETTE]`
+pub fn cli(args:&[OsString])->Result<()> {
+    let (world_path,sketch,palette) =
+	match args {
+	    [w,s] => (Path::new(w),Path::new(s),None),
+	    [w,s,p] => (Path::new(w),Path::new(s),Some(Path::new(p))),
+	    _ => bail!("Usage: mzg-editor --import WORLD SKETCH [PALETTE]")
+	};
+    let mut world = World::new();
+    if world_path.exists() {
+	world.load(world_path)?;
+    }
+    let id = import_room(&mut world,sketch,palette)?;
+    world.save(world_path)?;
+    println!("Imported {:?} as room {} of {:?}",sketch,id,world_path);
+    Ok(())
+}
@@ -2,7 +2,12 @@
 #![allow(unused_imports)]
 #![allow(dead_code)]
 
+mod autosave;
 mod common;
+mod config;
+mod import;
+mod play_test;
+mod player_link;
 mod refresher;
 mod tile_viewer;
 
@@ -13,26 +18,56 @@ use a2::A2;
 use tiles::{
     Corner,
     Door,
+    Requirement,
     Tile,
     Random
 };
-use object::Object;
+use goal::Goal;
+use creature::{
+    Behaviour,
+    Contact,
+    Creature
+};
+use dialogue::Dialogue;
+use vortex::Destination;
+use object::{
+    Object,
+    ObjectType
+};
+use mini_rng::MiniRNG;
+use sounds::Sounds;
 use world::{
     World,
     TileAddress
 };
 use room::Room;
+use inventory::Inventory;
+use facing::Facing;
 use tile_viewer::{
     TileViewer,
     Tool
 };
+use player_link::PlayerLink;
+use autosave::Autosave;
+use config::Config;
 use tiles::{
     Periodic,
-    Target
+    Target,
+    TileType
 };
 use ptr::*;
 
 fn main()->Result<(), eframe::Error> {
+    let args : Vec<_> = std::env::args_os().collect();
+    if args.get(1).map(|a| a == "--import").unwrap_or(false) {
+	if let Err(e) = import::cli(&args[2..]) {
+	    eprintln!("Error: {}",e);
+	    std::process::exit(1);
+	}
+	return Ok(());
+    }
+    let path = args.get(1).map(PathBuf::from);
+
     let options = eframe::NativeOptions {
 	viewport:ViewportBuilder::default()
 	    .with_maximized(true)
@@ -45,9 +80,9 @@ fn main()->Result<(), eframe::Error> {
     eframe::run_native(
 	"Mazegame Level Editor",
 	options,
-	Box::new(|cc| {
+	Box::new(move |cc| {
 	    egui_extras::install_image_loaders(&cc.egui_ctx);
-	    Box::new(Leved::new(cc))
+	    Box::new(Leved::new(cc,path))
 	}),
     )
 }
@@ -55,20 +90,51 @@ fn main()->Result<(), eframe::Error> {
 struct DoorEditor {
     room:usize,
     indices:(isize,isize),
+    /// Id of the door being edited, as it is in the world
+    id:usize,
+    /// Edited copy of the door
     door:Door,
 }
 
+/// Action waiting for the user to decide what to do with unsaved changes
+#[derive(Clone)]
+enum Confirm {
+    Load,
+    Open(PathBuf),
+    Quit
+}
+
 struct Leved {
     tex:Option<TextureHandle>,
     frame_rate:f32,
-    play:bool,
     tv:TileViewer,
+    link:PlayerLink,
     message:String,
     door_props_open:bool,
     path:Option<PathBuf>,
     door_editor:Option<DoorEditor>,
     delete_safety:bool,
-    crop_safety:bool
+    delete_clear_doors:bool,
+    crop_safety:bool,
+    compact_first:usize,
+    dirty:bool,
+    autosave:Autosave,
+    confirm:Option<Confirm>,
+    /// Autosave to offer for recovery, with the path of its world
+    recover:Option<(Option<PathBuf>,PathBuf)>,
+    config:Config,
+    allow_close:bool,
+    title:String,
+    /// Scripts being edited, one per line
+    scripts_text:Option<String>,
+    /// Sign or creature whose dialogue is being edited, with its text
+    dialogue_text:Option<(Speaker,String)>
+}
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+enum Speaker {
+    Sign(TileAddress),
+    Creature(usize)
 }
 
 fn using<T,F:FnMut(T)>(x:Option<T>,mut f:F) {
@@ -79,20 +145,40 @@ fn using<T,F:FnMut(T)>(x:Option<T>,mut f:F) {
 }
 
 impl Leved {
-    fn new(_cc:&eframe::CreationContext<'_>)->Self {
+    fn new(_cc:&eframe::CreationContext<'_>,path:Option<PathBuf>)->Self {
 	let tv = TileViewer::new();
-	Self {
+	let mut this = Self {
 	    tex:None,
 	    frame_rate:10.0,
-	    play:false,
 	    tv,
+	    link:PlayerLink::new(),
 	    message:String::new(),
 	    path:None,
 	    door_props_open:false,
 	    door_editor:None,
 	    delete_safety:false,
-	    crop_safety:false
+	    delete_clear_doors:true,
+	    crop_safety:false,
+	    compact_first:0,
+	    dirty:false,
+	    autosave:Autosave::new(Duration::from_secs(60)),
+	    confirm:None,
+	    recover:Autosave::recoverable(None).map(|a| (None,a)),
+	    config:Config::load(),
+	    allow_close:false,
+	    title:String::new(),
+	    scripts_text:None,
+	    dialogue_text:None
+	};
+	let path = path.or_else(|| {
+	    this.config.last()
+		.map(|r| r.path.clone())
+		.filter(|p| p.exists())
+	});
+	if let Some(path) = path {
+	    this.load(path);
 	}
+	this
     }
 
     fn message(&mut self,msg:&str) {
@@ -120,6 +206,7 @@ impl Leved {
 			d.key = Some(o);
 			d.locked = true;
 			self.tv.world.set_tile(&ta1,Tile::Door(d));
+			self.dirty = true;
 			return;
 		    },
 		    _ => ()
@@ -132,6 +219,7 @@ impl Leved {
     fn start(&mut self,_ui:&mut Ui) {
 	if let Some(ta) = self.tv.selection1() {
 	    self.tv.world.start = Some(ta);
+	    self.dirty = true;
 	    return;
 	}
 	self.message("Select starting position in green");
@@ -154,10 +242,14 @@ impl Leved {
 		return;
 	    }
 
-	    let room_ptr = self.tv.world.get_room(ta1.room_id);
-	    let mut room = room_ptr.yank_mut();
-	    room.crop(iy0,ny,ix0,nx);
-	    self.message(&format!("Room cropped to {} × {}",ny,nx));
+	    match self.tv.world.crop_room(ta1.room_id,iy0,ny,ix0,nx) {
+		Ok(()) => {
+		    self.dirty = true;
+		    self.message(&format!("Room cropped to {} × {}",ny,nx));
+		},
+		Err(e) => self.message(&format!("Cannot crop: {}",e))
+	    }
+	    return;
 	}
 	self.message("Select two corners");
     }
@@ -174,6 +266,7 @@ impl Leved {
 						  door:d1.id });
 			self.tv.world.set_tile(&ta1,Tile::Door(d1));
 			self.tv.world.set_tile(&ta2,Tile::Door(d2));
+			self.dirty = true;
 			self.message("Doors connected");
 		    },
 		    _ => {
@@ -184,39 +277,484 @@ impl Leved {
 	}
     }
 
-    fn save(&mut self,_ui:&mut Ui) {
+    /// Link the GREEN-selected vortex to the RED selection, both ways if
+    /// that is a vortex too
+    fn link_vortex(&mut self) {
+	match self.tv.selection1().zip(self.tv.selection2()) {
+	    None => self.message("Select a vortex in GREEN and its destination in RED"),
+	    Some((ta1,ta2)) => {
+		let res =
+		    if self.tv.world.get_tile(&ta2) == Some(Tile::Vortex) {
+			self.tv.world.link_vortices(ta1,ta2)
+			    .map(|()| "Vortices linked")
+		    } else {
+			self.tv.world.set_vortex(ta1,Some(Destination::Tile(ta2)))
+			    .map(|()| "Vortex leads to the RED selection")
+		    };
+		match res {
+		    Err(e) => self.message(&format!("Error: {}",e)),
+		    Ok(msg) => {
+			self.dirty = true;
+			self.message(msg);
+		    }
+		}
+	    }
+	}
+    }
+
+    fn set_vortex(&mut self,to:Option<Destination>) {
+	match self.tv.selection1() {
+	    None => self.message("Select a vortex in GREEN"),
+	    Some(ta) =>
+		match self.tv.world.set_vortex(ta,to) {
+		    Err(e) => self.message(&format!("Error: {}",e)),
+		    Ok(()) => {
+			self.dirty = true;
+			self.message(if to.is_some() { "Vortex leads to a random vortex" }
+				     else { "Vortex cleared" });
+		    }
+		}
+	}
+    }
+
+    fn edit_door(&mut self,ta:TileAddress) {
+	if let Some(Tile::Door(door)) = self.tv.world.get_tile(&ta) {
+	    self.door_editor = Some(DoorEditor {
+		room:ta.room_id,
+		indices:(ta.iy as isize,ta.ix as isize),
+		id:door.id,
+		door
+	    });
+	    self.door_props_open = true;
+	}
+    }
+
+    fn apply_door(&mut self) {
+	if let Some(ded) = self.door_editor.as_mut() {
+	    match self.tv.world.update_door(ded.room,ded.id,ded.door) {
+		Err(e) => self.message(&format!("Error: {}",e)),
+		Ok(()) => {
+		    ded.id = ded.door.id;
+		    self.dirty = true;
+		    let msg = format!("Door {} of room {} changed",ded.id,ded.room);
+		    self.message(&msg);
+		}
+	    }
+	}
+    }
+
+    fn door_window(&mut self,ctx:&Context) {
+	let mut apply = false;
+	let mut revert = false;
+	let mut goto = None;
+	let world = &self.tv.world;
+	Window::new("Edit door")
+	    .open(&mut self.door_props_open)
+	    .vscroll(false)
+	    .default_width(400.0)
+	    .default_height(300.0)
+	    .default_pos(ctx.screen_rect().center())
+	    .movable(true)
+	    .show(ctx, |ui| {
+		if let Some(ded) = self.door_editor.as_mut() {
+		    ui.label(format!("Room {} at ({:02},{:02})",
+				     ded.room,ded.indices.0,ded.indices.1));
+		    Grid::new("door_props")
+			.num_columns(2)
+			.show(ui,|ui| {
+			    ui.label("Door id:");
+			    ui.add(DragValue::new(&mut ded.door.id));
+			    ui.end_row();
+
+			    let room_name = |id:usize| {
+				world.rooms.get(&id)
+				    .map(|room_ptr| format!("{} {}",id,room_ptr.yank().name))
+				    .unwrap_or_else(|| format!("{} ???",id))
+			    };
+
+			    ui.label("Goes to room:");
+			    let mut target_room = ded.door.target.map(|t| t.room);
+			    ComboBox::from_id_source("door_target_room")
+				.selected_text(target_room.map(room_name)
+					       .unwrap_or_else(|| "Nowhere!".to_string()))
+				.show_ui(ui,|ui| {
+				    ui.selectable_value(&mut target_room,None,"Nowhere!");
+				    for id in world.room_list() {
+					ui.selectable_value(&mut target_room,Some(id),room_name(id));
+				    }
+				});
+			    ui.end_row();
+
+			    ui.label("Goes to door:");
+			    let mut target_door = ded.door.target.map(|t| t.door);
+			    if target_room != ded.door.target.map(|t| t.room) {
+				target_door = target_room
+				    .and_then(|room| world.door_list(room).first().copied());
+			    }
+			    ComboBox::from_id_source("door_target_door")
+				.selected_text(target_door.map(|d| format!("{}",d))
+					       .unwrap_or_else(|| "-".to_string()))
+				.show_ui(ui,|ui| {
+				    if let Some(room) = target_room {
+					for d in world.door_list(room) {
+					    ui.selectable_value(&mut target_door,Some(d),format!("{}",d));
+					}
+				    }
+				});
+			    ui.end_row();
+			    ded.door.target = target_room.zip(target_door)
+				.map(|(room,door)| Target { room,door });
+
+			    ui.label("Key:");
+			    ComboBox::from_id_source("door_key")
+				.selected_text(ded.door.key.map(|o| world.object_kind(o).name)
+					       .unwrap_or_else(|| "None".to_string()))
+				.show_ui(ui,|ui| {
+				    ui.selectable_value(&mut ded.door.key,None,"None");
+				    for ot in world.objects.iter().filter(|ot| ot.key) {
+					ui.selectable_value(&mut ded.door.key,Some(ot.object()),&ot.name);
+				    }
+				});
+			    ui.end_row();
+
+			    ui.label("Locked:");
+			    ui.checkbox(&mut ded.door.locked,"");
+			    ui.end_row();
+
+			    ui.label("Requires:");
+			    ui.horizontal(|ui| {
+				let (mut kind,mut count) =
+				    match ded.door.requires {
+					None => ("NONE",0),
+					Some(Requirement::Coins { count,consume:false }) => ("COINS",count),
+					Some(Requirement::Coins { count,consume:true }) => ("TOLL",count),
+					Some(Requirement::Foods { count }) => ("FOODS",count)
+				    };
+				ComboBox::from_id_source("door_requires")
+				    .selected_text(kind)
+				    .show_ui(ui,|ui| {
+					for k in ["NONE","COINS","TOLL","FOODS"] {
+					    ui.selectable_value(&mut kind,k,k);
+					}
+				    });
+				if kind != "NONE" {
+				    ui.add(DragValue::new(&mut count));
+				}
+				ded.door.requires = Requirement::parse(kind,count);
+			    });
+			    ui.end_row();
+			});
+
+		    ui.separator();
+		    ui.horizontal(|ui| {
+			if ui.button("APPLY").clicked() {
+			    apply = true;
+			}
+			if ui.button("REVERT").clicked() {
+			    revert = true;
+			}
+			if let Some(Target { room, .. }) = ded.door.target {
+			    if ui.button("GO TO TARGET").clicked() {
+				goto = Some(room);
+			    }
+			}
+		    });
+		} else {
+		    ui.label("We are not editing a door right now.");
+		}
+	    });
+	if apply {
+	    self.apply_door();
+	}
+	if revert {
+	    if let Some(ded) = self.door_editor.as_mut() {
+		if let Some(door) = self.tv.world.get_door(ded.room,ded.id) {
+		    ded.door = door;
+		}
+	    }
+	}
+	if let Some(room_id) = goto {
+	    self.goto_room(room_id);
+	}
+    }
+
+    fn disconnect(&mut self,_ui:&mut Ui) {
+	if let Some(ta) = self.tv.selection1() {
+	    if let Some(Tile::Door(d)) = self.tv.world.get_tile(&ta) {
+		match self.tv.world.disconnect(ta.room_id,d.id) {
+		    Err(e) => self.message(&format!("Error: {}",e)),
+		    Ok(None) => self.message("Door was not connected"),
+		    Ok(Some(Target { room,door })) => {
+			self.dirty = true;
+			self.message(&format!("Door disconnected from door {} of room {}",
+					      door,room))
+		    }
+		}
+		return;
+	    }
+	}
+	self.message("Select a door in green");
+    }
+
+    fn reconnect(&mut self,_ui:&mut Ui) {
+	if let Some((ta1,ta2)) = self.tv.selection1().zip(self.tv.selection2()) {
+	    if let Some((Tile::Door(d1),Tile::Door(d2))) =
+		self.tv.world.get_tile(&ta1).zip(self.tv.world.get_tile(&ta2)) {
+		match self.tv.world.reconnect(ta1.room_id,d1.id,ta2.room_id,d2.id) {
+		    Err(e) => self.message(&format!("Error: {}",e)),
+		    Ok(()) => {
+			self.dirty = true;
+			self.message("Doors reconnected")
+		    }
+		}
+		return;
+	    }
+	}
+	self.message("Select doors in green and red");
+    }
+
+    fn test_play(&mut self,_ui:&mut Ui) {
+	match self.tv.selection1().or(self.tv.world.start) {
+	    None => self.message("Select a starting position in green or set START"),
+	    Some(ta) => {
+		if self.tv.world.get_tile(&ta).is_none() {
+		    self.message("Starting position is not in a room");
+		    return;
+		}
+		self.tv.start_play(ta);
+		self.message("Play-testing: arrows to move, space to drop, STOP to return to editing");
+	    }
+	}
+    }
+
+    fn save(&mut self) {
 	if let Some(path) = self.path.as_ref() {
 	    match self.tv.world.save(path) {
 		Err(e) => self.message(&format!("Error: {}",e)),
 		Ok(()) => {
-		    self.message(&format!("Saved under {:?}",path));
+		    Autosave::remove(Some(path));
+		    self.dirty = false;
+		    let dangling = self.tv.world.dangling_vortices().len();
+		    if dangling > 0 {
+			self.message(&format!("Saved under {:?}; warning: {} vortices lead nowhere",
+					      path,dangling));
+		    } else {
+			self.message(&format!("Saved under {:?}",path));
+		    }
 		}
 	    }
 	}
     }
 
-    fn play(&mut self,_ui:&mut Ui) {
-	use std::process::Command;
-	use std::env::Vars;
+    fn load_dialog(&mut self) {
+	let rfd = rfd::FileDialog::new()
+	    .set_title("Load world");
 
-	if let Some(path) = self.path.as_ref() {
-	    match std::env::var("PLAYER_COMMAND") {
-		Ok(cmd) => {
-		    match Command::new(cmd)
-			.arg(path)
-			.status() {
-			    Ok(status) => {
-				if !status.success() {
-				    self.message("Player failed");
-				}
-			    },
+	let rfd =
+	    if let Some(path_s) = self.path_so() {
+		rfd.set_file_name(path_s)
+	    } else {
+		rfd
+	    };
+
+	if let Some(path) = rfd.pick_file() {
+	    self.load(path);
+	}
+    }
+
+    fn load(&mut self,path:PathBuf) {
+	self.remember_room();
+	self.tv.world.clear();
+	self.tv.set_room(None);
+	self.scripts_text = None;
+	self.dialogue_text = None;
+	match self.tv.world.load(&path) {
+	    Err(e) => self.message(&format!("Error: {}",e)),
+	    Ok(()) => {
+		self.message(&format!("Loaded from {:?}",path));
+		if let Some(autosave) = Autosave::recoverable(Some(&path)) {
+		    self.recover = Some((Some(path.clone()),autosave));
+		}
+		self.config.opened(&path);
+		self.save_config();
+		self.path = Some(path);
+		self.dirty = false;
+		if let Some(TileAddress { room_id, .. }) = self.tv.world.start {
+		    self.goto_room(room_id);
+		}
+		if let Some(room_id) = self.config.last().and_then(|r| r.room) {
+		    self.goto_room(room_id);
+		}
+	    }
+	}
+    }
+
+    /// Record the room being edited in the recent list
+    fn remember_room(&mut self) {
+	if let Some((path,room_ptr)) = self.path.as_ref().zip(self.tv.room()) {
+	    self.config.set_room(path,room_ptr.yank().id);
+	}
+    }
+
+    fn save_config(&mut self) {
+	if let Err(e) = self.config.save() {
+	    self.message(&format!("Cannot save configuration: {}",e));
+	}
+    }
+
+    fn recover(&mut self,path:Option<PathBuf>,autosave:&Path) {
+	self.tv.world.clear();
+	self.tv.set_room(None);
+	self.scripts_text = None;
+	self.dialogue_text = None;
+	match self.tv.world.load(autosave) {
+	    Err(e) => self.message(&format!("Error: {}",e)),
+	    Ok(()) => {
+		self.message(&format!("Recovered from {:?}",autosave));
+		self.path = path;
+		self.dirty = true;
+		if let Some(TileAddress { room_id, .. }) = self.tv.world.start {
+		    self.goto_room(room_id);
+		}
+	    }
+	}
+    }
+
+    fn do_autosave(&mut self) {
+	if self.dirty && self.autosave.due() {
+	    let autosave = Autosave::path_for(self.path.as_deref());
+	    if let Err(e) = self.tv.world.save(&autosave) {
+		self.message(&format!("Autosave error: {}",e));
+	    }
+	}
+    }
+
+    fn update_title(&mut self,ctx:&Context) {
+	let name = self.path.as_ref()
+	    .and_then(|p| p.file_name())
+	    .map(|n| n.to_string_lossy().to_string())
+	    .unwrap_or_else(|| "untitled".to_string());
+	let title = format!("Mazegame Level Editor - {}{}",name,
+			    if self.dirty { " *" } else { "" });
+	if title != self.title {
+	    ctx.send_viewport_cmd(ViewportCommand::Title(title.clone()));
+	    self.title = title;
+	}
+    }
+
+    fn proceed(&mut self,ctx:&Context,confirm:Confirm) {
+	match confirm {
+	    Confirm::Load => self.load_dialog(),
+	    Confirm::Open(path) => self.load(path),
+	    Confirm::Quit => {
+		self.allow_close = true;
+		ctx.send_viewport_cmd(ViewportCommand::Close);
+	    }
+	}
+    }
+
+    fn confirm_window(&mut self,ctx:&Context) {
+	if let Some(confirm) = self.confirm.clone() {
+	    let mut choice = None;
+	    Window::new("Unsaved changes")
+		.collapsible(false)
+		.resizable(false)
+		.anchor(Align2::CENTER_CENTER,vec2(0.0,0.0))
+		.show(ctx,|ui| {
+		    ui.label("The world has unsaved changes.");
+		    ui.horizontal(|ui| {
+			if self.path.is_some() && ui.button("SAVE").clicked() {
+			    choice = Some(true);
+			}
+			if ui.button("DISCARD").clicked() {
+			    choice = Some(false);
+			}
+			if ui.button("CANCEL").clicked() {
+			    self.confirm = None;
+			}
+		    });
+		});
+	    if let Some(save) = choice {
+		self.confirm = None;
+		if save {
+		    self.save();
+		}
+		if !save || !self.dirty {
+		    self.proceed(ctx,confirm);
+		}
+	    }
+	}
+    }
+
+    fn recover_window(&mut self,ctx:&Context) {
+	if let Some((path,autosave)) = self.recover.clone() {
+	    let mut choice = None;
+	    Window::new("Recover autosave")
+		.collapsible(false)
+		.resizable(false)
+		.anchor(Align2::CENTER_CENTER,vec2(0.0,0.0))
+		.show(ctx,|ui| {
+		    ui.label(format!("An autosave more recent than the world was found in {:?}.",
+				     autosave));
+		    ui.horizontal(|ui| {
+			if ui.button("RECOVER").clicked() {
+			    choice = Some(true);
+			}
+			if ui.button("DISCARD").clicked() {
+			    choice = Some(false);
+			}
+		    });
+		});
+	    if let Some(recover) = choice {
+		self.recover = None;
+		if recover {
+		    self.recover(path,&autosave);
+		} else {
+		    Autosave::remove(path.as_deref());
+		}
+	    }
+	}
+    }
+
+    fn play(&mut self,_ui:&mut Ui) {
+	match self.link.send(link::Command::World(Box::new(self.tv.world.deep_copy()))) {
+	    Ok(()) => self.message("World sent to the player"),
+	    Err(_) => {
+		match self.path.clone() {
+		    None => {
+			self.link.cancel();
+			self.message("Save the world first so that the player can be launched");
+		    },
+		    Some(path) => {
+			match self.link.launch(&path) {
+			    Ok(()) => self.message("Launching the player..."),
 			    Err(e) => {
-				self.message(&format!("Could not launch player: {}",e));
+				self.link.cancel();
+				self.message(&format!("Error: {}",e));
 			    }
 			}
-		},
-		Err(_) => {
-		    self.message("No PLAYER_COMMAND environment variable");
+		    }
+		}
+	    }
+	}
+    }
+
+    fn teleport(&mut self,_ui:&mut Ui) {
+	match self.tv.selection1() {
+	    None => self.message("Select the destination of the hero in green"),
+	    Some(ta) => {
+		let cmd = link::Command::Teleport(ta);
+		if self.link.is_pending() {
+		    self.link.queue(cmd);
+		    self.message("Hero will be teleported once the player is up");
+		} else {
+		    match self.link.send(cmd) {
+			Ok(()) => self.message("Hero teleported"),
+			Err(e) => {
+			    self.link.cancel();
+			    self.message(&format!("Error: {}, press PLAY first",e));
+			}
+		    }
 		}
 	    }
 	}
@@ -242,57 +780,51 @@ const TILE_PALETTE : & [(&str,Tool,&str)] = &[
     ("A",Tool::Place(Tile::Alien),"ALIEN"),
     ("x",Tool::Place(Tile::MetalFoot),"METAL FOOT"),
     ("^",Tool::PlaceSky,"SKY"),
-    ("D",Tool::Place(Tile::Door(Door { id:0,target:None,key:None,locked:false })),"DOOR"),
-    ("K",Tool::Place(Tile::Object(Object::Key)),"KEY"),
-    ("T",Tool::Place(Tile::Object(Object::ToyCar)),"TOY CAR"),
-    ("I",Tool::Place(Tile::Object(Object::IceCream)),"ICECREAM"),
-    ("C",Tool::Place(Tile::Object(Object::Coin)),"COIN"),
-    ("S",Tool::Place(Tile::Object(Object::SquaresAndTriangles)),"SQ&TR"),
-    ("c",Tool::Place(Tile::Object(Object::Carrot)),"CARROT"),
-    ("t",Tool::Place(Tile::Object(Object::Tomato)),"TOMATO"),
-    ("e",Tool::Place(Tile::Object(Object::Eggplant)),"EGGPLANT"),
-    ("b",Tool::Place(Tile::Object(Object::Banana)),"BANANA"),
+    ("D",Tool::Place(Tile::Door(Door { id:0,target:None,key:None,locked:false,requires:None })),"DOOR"),
     ("R",Tool::Place(Tile::Rainbow),"RAINBOW"),
+    ("B",Tool::Place(Tile::Crate { on_plate:false }),"CRATE"),
+    ("_",Tool::Place(Tile::Plate),"PLATE"),
+    ("&",Tool::Place(Tile::Crate { on_plate:true }),"CRATE ON PLATE"),
+    ("/",Tool::Place(Tile::Lever { on:false }),"LEVER"),
+    ("$",Tool::Place(Tile::CoinSlot { paid:false }),"COIN SLOT"),
+    ("!",Tool::Place(Tile::Sign),"SIGN"),
+    ("▒",Tool::Place(Tile::Ice),"ICE"),
+    ("⇑",Tool::Place(Tile::Conveyor(Facing::Up)),"CONVEYOR UP"),
+    ("⇓",Tool::Place(Tile::Conveyor(Facing::Down)),"CONVEYOR DOWN"),
+    ("⇐",Tool::Place(Tile::Conveyor(Facing::Left)),"CONVEYOR LEFT"),
+    ("⇒",Tool::Place(Tile::Conveyor(Facing::Right)),"CONVEYOR RIGHT"),
+    ("↑",Tool::Place(Tile::OneWay(Facing::Up)),"ONE WAY UP"),
+    ("↓",Tool::Place(Tile::OneWay(Facing::Down)),"ONE WAY DOWN"),
+    ("←",Tool::Place(Tile::OneWay(Facing::Left)),"ONE WAY LEFT"),
+    ("→",Tool::Place(Tile::OneWay(Facing::Right)),"ONE WAY RIGHT"),
     ("L",Tool::Lock,"LOCK"),
     ("U",Tool::Unlock,"UNLOCK"),
 ];
 
 impl eframe::App for Leved {
+    fn on_exit(&mut self,_gl:Option<&glow::Context>) {
+	self.remember_room();
+	let _ = self.config.save();
+    }
+
     fn update(&mut self,ctx:&Context,_frame:&mut eframe::Frame) {
+	self.link.poll();
+	if self.link.is_pending() {
+	    ctx.request_repaint_after(Duration::from_millis(250));
+	}
+	if self.tv.take_modified() {
+	    self.dirty = true;
+	}
+	self.do_autosave();
+	self.update_title(ctx);
+	if ctx.input(|i| i.viewport().close_requested()) && self.dirty && !self.allow_close {
+	    ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+	    self.confirm = Some(Confirm::Quit);
+	}
+	self.confirm_window(ctx);
+	self.recover_window(ctx);
+	self.door_window(ctx);
 	CentralPanel::default().show(ctx,|ui| {
-	    Window::new("Edit door")
-		.open(&mut self.door_props_open)
-		.vscroll(false)
-		.default_width(400.0)
-		.default_height(300.0)
-		.default_pos(ctx.screen_rect().center())
-		.movable(true)
-		.show(ctx, |ui| {
-		    if let Some(ded) = self.door_editor.as_mut() {
-			ui.horizontal(|ui| {
-			    let target =
-				if let Some(Target { room,door }) = ded.door.target {
-				    format!("Room {} door {}",room,door)
-				} else {
-				    format!("Nowhere!")
-				};
-			    ui.label(format!("Goes to: {}",target));
-			    if ui.button("Change").clicked() {
-			    }
-			});
-
-			ui.checkbox(&mut ded.door.locked,"Locked");
-		    } else {
-			ui.label("We are not editing a door right now.");
-		    }
-		    if ui.button("Change the door").clicked() {
-			// if let Some(room_ptr) = self.tv.room() {
-			// 	let mut room = room_ptr.yank_mut();
-			// 	if let Some((iy,ix)) = self.tv.selection1() {
-			// 	}
-			// }
-		    }
-		});
 	    StripBuilder::new(ui)
 		.size(Size::remainder().at_least(700.0))
 		.size(Size::exact(300.0))
@@ -303,7 +835,9 @@ impl eframe::App for Leved {
 				if let Some(room_ptr) = self.tv.room() {
 				    ui.label("Name:");
 				    let mut room = room_ptr.yank_mut();
-				    ui.text_edit_singleline(&mut room.name);
+				    if ui.text_edit_singleline(&mut room.name).changed() {
+					self.dirty = true;
+				    }
 				} else {
 				    ui.label("No room, create or select one");
 				}
@@ -315,12 +849,39 @@ impl eframe::App for Leved {
 					    if ui.button("CONN").clicked() {
 						self.connect(ui);
 					    }
+					    if ui.button("RECONN").clicked() {
+						self.reconnect(ui);
+					    }
+					    if ui.button("DISC").clicked() {
+						self.disconnect(ui);
+					    }
+					    if ui.button("VLINK").clicked() {
+						self.link_vortex();
+					    }
+					    if ui.button("VRANDOM").clicked() {
+						self.set_vortex(Some(Destination::Random));
+					    }
+					    if ui.button("VCLEAR").clicked() {
+						self.set_vortex(None);
+					    }
 					    if ui.button("UDW").clicked() {
 						self.udw(ui);
 					    }
 					    if ui.button("START").clicked() {
 						self.start(ui);
 					    }
+					    if ui.button("CHECKPOINT").clicked() {
+						self.checkpoint();
+					    }
+					    if ui.button("WIRE").clicked() {
+						self.wire();
+					    }
+					    if ui.button("CREATURE").clicked() {
+						self.creature();
+					    }
+					    if ui.button("TEXT").clicked() {
+						self.edit_dialogue();
+					    }
 					    if ui.button("CROP").clicked() {
 						self.crop(ui);
 					    }
@@ -331,8 +892,8 @@ impl eframe::App for Leved {
 			    ui.separator();
 
 			    self.tv.ui(ui);
-			    if let Some(room_id) = self.tv.take_goto() {
-				self.goto_room(room_id);
+			    if let Some(ta) = self.tv.take_door_edit() {
+				self.edit_door(ta);
 			    }
 			    // ScrollArea::both()
 			    // 	.scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible)
@@ -349,9 +910,14 @@ impl eframe::App for Leved {
 				..Default::default()
 			    };
 
-			    let events = ui.input(
-				|i|
-				i.filtered_events(&event_filter));
+			    let events =
+				if self.tv.is_playing() {
+				    Vec::new()
+				} else {
+				    ui.input(
+					|i|
+					i.filtered_events(&event_filter))
+				};
 			    for event in &events {
 				match event {
 				    Event::Text(u) => {
@@ -359,13 +925,17 @@ impl eframe::App for Leved {
 					    "u" => self.tv.undo(),
 					    "r" => self.tv.redo(),
 					    _ => {
-						let tm = self.tv.get_tool_mut();
-
-						for &(key,tool,_) in TILE_PALETTE {
-						    if key == u {
-							*tm = tool;
-							break;
-						    }
+						let objects = self.tv.world.objects.iter()
+						    .map(|ot| (ot.code.to_string(),Tool::Place(Tile::Object(ot.object()))));
+						let terrains = self.tv.world.tile_types.iter()
+						    .map(|tt| (tt.code.to_string(),Tool::Place(tt.tile())));
+						let tool = TILE_PALETTE.iter()
+						    .map(|&(key,tool,_)| (key.to_string(),tool))
+						    .chain(objects)
+						    .chain(terrains)
+						    .find(|(key,_)| key == u);
+						if let Some((_,tool)) = tool {
+						    *self.tv.get_tool_mut() = tool;
 						}
 					    }
 					}
@@ -377,6 +947,11 @@ impl eframe::App for Leved {
 			    ui.separator();
 			    ui.label(&self.message);
 
+			    let objects : Vec<(String,Tool,String)> = self.tv.world.objects.iter()
+				.map(|ot| (ot.code.to_string(),Tool::Place(Tile::Object(ot.object())),ot.name.clone()))
+				.chain(self.tv.world.tile_types.iter()
+				       .map(|tt| (tt.code.to_string(),Tool::Place(tt.tile()),tt.name.clone())))
+				.collect();
 			    let tm = self.tv.get_tool_mut();
 			    ui.separator();
 			    let num_rows = 8;
@@ -385,7 +960,9 @@ impl eframe::App for Leved {
 				    ui,
 				    |ui| {
 					let mut j = 0;
-					for &(key,tool,name) in TILE_PALETTE {
+					let fixed = TILE_PALETTE.iter()
+					    .map(|&(key,tool,name)| (key.to_string(),tool,name.to_string()));
+					for (key,tool,name) in fixed.chain(objects) {
 					    ui.selectable_value(
 						tm,
 						tool,
@@ -401,11 +978,22 @@ impl eframe::App for Leved {
 			    ui.separator();
 			    ui.horizontal(|ui| {
 				if ui.button("SAVE").clicked() {
-				    self.save(ui);
+				    self.save();
 				}
 				if ui.button("PLAY").clicked() {
 				    self.play(ui);
 				}
+				if ui.button("TELEPORT").clicked() {
+				    self.teleport(ui);
+				}
+				if self.tv.is_playing() {
+				    if ui.button("STOP").clicked() {
+					self.tv.stop_play();
+					self.message("Play-test stopped");
+				    }
+				} else if ui.button("TEST").clicked() {
+				    self.test_play(ui);
+				}
 				if ui.button("SAVE AS").clicked() {
 				    let rfd =
 					rfd::FileDialog::new()
@@ -426,37 +1014,42 @@ impl eframe::App for Leved {
 					};
 
 				    if let Some(path) = rfd.save_file() {
+					if self.path.is_none() {
+					    Autosave::remove(None);
+					}
+					self.remember_room();
+					self.config.opened(&path);
 					self.path = Some(path);
-					self.save(ui);
+					self.save();
+					self.remember_room();
+					self.save_config();
 				    }
 				}
 				if ui.button("LOAD").clicked() {
-				    let rfd = rfd::FileDialog::new()
-					.set_title("Load world");
-
-				    let rfd =
-					if let Some(path_s) = self.path_so() {
-					    rfd.set_file_name(path_s)
-					} else {
-					    rfd
-					};
-
-				    let patho = rfd.pick_file();
-
-				    if let Some(path) = patho {
-					self.tv.world.clear();
-					match self.tv.world.load(&path) {
-					    Err(e) => self.message(&format!("Error: {}",e)),
-					    Ok(()) => {
-						self.message(&format!("Loaded from {:?}",path));
-						self.path = Some(path);
-						if let Some(TileAddress { room_id, .. }) = self.tv.world.start {
-						    self.goto_room(room_id);
-						}
+				    if self.dirty {
+					self.confirm = Some(Confirm::Load);
+				    } else {
+					self.load_dialog();
+				    }
+				}
+				menu::menu_button(ui,"RECENT",|ui| {
+				    let recent : Vec<PathBuf> = self.config.recent.iter()
+					.map(|r| r.path.clone())
+					.collect();
+				    if recent.is_empty() {
+					ui.label("No recent worlds");
+				    }
+				    for path in recent {
+					if ui.button(path.display().to_string()).clicked() {
+					    ui.close_menu();
+					    if self.dirty {
+						self.confirm = Some(Confirm::Open(path));
+					    } else {
+						self.load(path);
 					    }
 					}
 				    }
-				}
+				});
 			    });
 			});
 		    });
@@ -500,21 +1093,25 @@ impl Leved {
 				.unwrap_or(0);
 			    let room = Room::empty(id,48,48);
 			    self.tv.world.insert_room(room);
+			    self.dirty = true;
 			    self.goto_room(id);
 			}
+			if ui.button("DUPLICATE").clicked() {
+			    self.duplicate_room();
+			}
+			if ui.button("IMPORT").clicked() {
+			    self.import_room();
+			}
 			let delete_safety = self.delete_safety;
 			if delete_safety {
 			    if ui.button("CONFIRM DELETE").clicked() {
-				if let Some(room_ptr) = self.tv.room() {
-				    let room = room_ptr.yank();
-				    self.tv.world.delete_room(room.id);
-				    self.tv.set_room(None);
-				}
+				self.delete_room();
 				self.delete_safety = false;
 			    }
 			    if ui.button("CANCEL DELETE").clicked() {
 				self.delete_safety = false;
 			    }
+			    ui.checkbox(&mut self.delete_clear_doors,"CLEAR DOORS");
 			} else {
 			    if ui.button("DELETE ROOM").clicked() {
 				self.delete_safety = true;
@@ -523,6 +1120,33 @@ impl Leved {
 		    });
 		});
 	});
+	ui.horizontal(|ui| {
+	    if ui.button("RENUMBER FROM").clicked() {
+		self.compact_rooms();
+	    }
+	    ui.add(DragValue::new(&mut self.compact_first));
+	});
+	ui.horizontal(|ui| {
+	    ui.label("INVENTORY SLOTS");
+	    if ui.add(DragValue::new(&mut self.tv.world.inventory_capacity)
+		      .clamp_range(1..=Inventory::MAX_CAPACITY)).changed() {
+		self.dirty = true;
+	    }
+	});
+	ui.separator();
+	self.goals_ui(ui);
+	ui.separator();
+	self.hazards_ui(ui);
+	ui.separator();
+	self.objects_ui(ui);
+	ui.separator();
+	self.tile_types_ui(ui);
+	ui.separator();
+	self.scripts_ui(ui);
+	ui.separator();
+	self.creatures_ui(ui);
+	ui.separator();
+	self.dialogue_ui(ui);
 	ui.separator();
 	let active_id = self.tv.room().map(|p| p.yank().id);
 	let room_list = self.tv.world.room_list();
@@ -556,6 +1180,659 @@ impl Leved {
 	};
     }
 
+    fn delete_room(&mut self) {
+	if let Some(room_ptr) = self.tv.room() {
+	    let id = room_ptr.yank().id;
+	    let had_start = self.tv.world.start.map(|ta| ta.room_id) == Some(id);
+	    let goals = self.tv.world.goals.len();
+	    let vortices = self.tv.world.incoming_vortices(id);
+	    let incoming = self.tv.world.delete_room(id,self.delete_clear_doors);
+	    self.dirty = true;
+	    self.tv.forget_room(id);
+	    let mut msg = format!("Room {} deleted",id);
+	    if !incoming.is_empty() {
+		let doors : Vec<String> = incoming.iter()
+		    .map(|(room,door)| format!("{}/{}",room,door))
+		    .collect();
+		write!(msg,"; {} {} leading to it: {}",
+		       if self.delete_clear_doors { "disconnected" } else { "dangling" },
+		       if incoming.len() == 1 { "door" } else { "doors" },
+		       doors.join(" ")).unwrap();
+	    }
+	    if !vortices.is_empty() {
+		let links : Vec<String> = vortices.iter()
+		    .map(|ta| format!("{}/({},{})",ta.room_id,ta.iy,ta.ix))
+		    .collect();
+		write!(msg,"; {} leading to it cleared: {}",
+		       if vortices.len() == 1 { "vortex" } else { "vortices" },
+		       links.join(" ")).unwrap();
+	    }
+	    if had_start {
+		write!(msg,"; start position removed").unwrap();
+	    }
+	    let removed = goals - self.tv.world.goals.len();
+	    if removed > 0 {
+		write!(msg,"; {} {} removed",removed,if removed == 1 { "goal" } else { "goals" }).unwrap();
+	    }
+	    self.message(&msg);
+	}
+    }
+
+    fn import_room(&mut self) {
+	let sketch = rfd::FileDialog::new()
+	    .set_title("Import room from sketch")
+	    .add_filter("Sketches",&["txt","png"])
+	    .pick_file();
+	if let Some(sketch) = sketch {
+	    let palette =
+		if import::needs_palette(&sketch) {
+		    match rfd::FileDialog::new()
+			.set_title("Palette for the sketch")
+			.pick_file() {
+			    None => return,
+			    palette => palette
+			}
+		} else {
+		    None
+		};
+	    match import::import_room(&mut self.tv.world,&sketch,palette.as_deref()) {
+		Err(e) => self.message(&format!("Error: {}",e)),
+		Ok(id) => {
+		    self.dirty = true;
+		    self.goto_room(id);
+		    self.message(&format!("Imported {:?} as room {}",sketch,id));
+		}
+	    }
+	}
+    }
+
+    fn duplicate_room(&mut self) {
+	if let Some(room_ptr) = self.tv.room() {
+	    let id = room_ptr.yank().id;
+	    if let Some(new_id) = self.tv.world.duplicate_room(id) {
+		self.dirty = true;
+		self.goto_room(new_id);
+		self.message(&format!("Room {} duplicated as {}",id,new_id));
+	    }
+	} else {
+	    self.message("Select a room to duplicate");
+	}
+    }
+
+    fn compact_rooms(&mut self) {
+	match self.tv.world.compact(self.compact_first) {
+	    Err(e) => self.message(&format!("Error: {}",e)),
+	    Ok(remap) => {
+		self.dirty = true;
+		self.tv.remap_rooms(&remap);
+		if let Some(ded) = self.door_editor.as_mut() {
+		    ded.room = remap.get(&ded.room).copied().unwrap_or(ded.room);
+		    if let Some(t) = ded.door.target.as_mut() {
+			t.room = remap.get(&t.room).copied().unwrap_or(t.room);
+		    }
+		}
+		self.message(&format!("Rooms renumbered from {}",self.compact_first));
+	    }
+	}
+    }
+
+    fn goals_ui(&mut self,ui:&mut Ui) {
+	let mut new_goal = None;
+	let first_object = self.tv.world.objects.first().map(|ot| ot.object()).unwrap_or(Object::KEY);
+	ui.horizontal(|ui| {
+	    ui.label("Goals");
+	    if self.tv.world.goals.is_empty() {
+		ui.label("(default: reach a rainbow)");
+	    }
+	    ui.with_layout(
+		Layout::right_to_left(Align::Center),
+		|ui| {
+		    menu::menu_button(ui,"ADD GOAL",|ui| {
+			let here = self.tv.selection1().or(self.tv.world.start);
+			let room = here.map(|ta| ta.room_id).unwrap_or(0);
+			if ui.button("REACH RAINBOW").clicked() {
+			    new_goal = Some(Goal::ReachRainbow);
+			}
+			if ui.button("COLLECT ALL COINS").clicked() {
+			    new_goal = Some(Goal::CollectAllCoins);
+			}
+			if ui.button("COVER ALL PLATES").clicked() {
+			    new_goal = Some(Goal::CoverAllPlates);
+			}
+			if ui.button("COLLECT OBJECTS").clicked() {
+			    new_goal = Some(Goal::Collect { object:first_object,count:1 });
+			}
+			if let Some(to) = here {
+			    if ui.button("DELIVER TO GREEN").clicked() {
+				new_goal = Some(Goal::Deliver { object:first_object,to });
+			    }
+			}
+			if ui.button("REACH ROOM").clicked() {
+			    new_goal = Some(Goal::ReachRoom { room,within:None });
+			}
+			if new_goal.is_some() {
+			    ui.close_menu();
+			}
+		    });
+		});
+	});
+	if let Some(goal) = new_goal {
+	    self.tv.world.goals.push(goal);
+	    self.dirty = true;
+	}
+
+	let objects = self.tv.world.objects.clone();
+	let object_box = |ui:&mut Ui,id:usize,object:&mut Object| {
+	    let mut changed = false;
+	    let name = objects.iter().find(|ot| ot.object() == *object)
+		.map(|ot| ot.name.as_str()).unwrap_or("???");
+	    ComboBox::from_id_source(("goal_object",id))
+		.selected_text(name)
+		.show_ui(ui,|ui| {
+		    for ot in objects.iter() {
+			changed |= ui.selectable_value(object,ot.object(),&ot.name).changed();
+		    }
+		});
+	    changed
+	};
+	let green = self.tv.selection1();
+	let mut delete = None;
+	let mut changed = false;
+	for (i,goal) in self.tv.world.goals.iter_mut().enumerate() {
+	    ui.horizontal(|ui| {
+		if ui.button("DEL").clicked() {
+		    delete = Some(i);
+		}
+		match goal {
+		    Goal::ReachRainbow => { ui.label("REACH THE RAINBOW"); },
+		    Goal::CollectAllCoins => { ui.label("COLLECT ALL COINS"); },
+		    Goal::CoverAllPlates => { ui.label("PUT A CRATE ON EVERY PLATE"); },
+		    Goal::Collect { object,count } => {
+			ui.label("COLLECT");
+			changed |= ui.add(DragValue::new(count).clamp_range(1..=999)).changed();
+			changed |= object_box(ui,i,object);
+		    },
+		    Goal::Deliver { object,to } => {
+			ui.label("BRING");
+			changed |= object_box(ui,i,object);
+			ui.label(format!("TO {} ({},{})",to.room_id,to.iy,to.ix));
+			if let Some(ta) = green {
+			    if ui.button("SET TO GREEN").clicked() {
+				*to = ta;
+				changed = true;
+			    }
+			}
+		    },
+		    Goal::ReachRoom { room,within } => {
+			ui.label("REACH ROOM");
+			changed |= ui.add(DragValue::new(room)).changed();
+			let mut limited = within.is_some();
+			if ui.checkbox(&mut limited,"IN").changed() {
+			    *within = if limited { Some(60) } else { None };
+			    changed = true;
+			}
+			if let Some(s) = within.as_mut() {
+			    changed |= ui.add(DragValue::new(s).clamp_range(1..=3600)).changed();
+			    ui.label("S");
+			}
+		    }
+		}
+	    });
+	}
+	if let Some(i) = delete {
+	    self.tv.world.goals.remove(i);
+	    changed = true;
+	}
+	if changed {
+	    self.dirty = true;
+	}
+    }
+
+    fn hazards_ui(&mut self,ui:&mut Ui) {
+	let objects = &self.tv.world.objects;
+	let hz = &mut self.tv.world.hazards;
+	let mut changed = false;
+	changed |= ui.checkbox(&mut hz.enabled,"Hazards: fire hurts, water needs a boat").changed();
+	if hz.enabled {
+	    ui.horizontal(|ui| {
+		ui.label("HEALTH");
+		changed |= ui.add(DragValue::new(&mut hz.health).clamp_range(1..=99)).changed();
+		let mut limited = hz.lives.is_some();
+		if ui.checkbox(&mut limited,"LIVES").changed() {
+		    hz.lives = if limited { Some(3) } else { None };
+		    changed = true;
+		}
+		if let Some(n) = hz.lives.as_mut() {
+		    changed |= ui.add(DragValue::new(n).clamp_range(1..=99)).changed();
+		}
+	    });
+	    ui.horizontal(|ui| {
+		ui.label("FIRE DAMAGE");
+		changed |= ui.add(DragValue::new(&mut hz.fire_damage).clamp_range(0..=99)).changed();
+		ui.label("IN PHASES");
+		for phase in 0..3 {
+		    let mut on = hz.fire_phases.contains(&phase);
+		    if ui.checkbox(&mut on,format!("{}",phase)).changed() {
+			if on {
+			    hz.fire_phases.push(phase);
+			} else {
+			    hz.fire_phases.retain(|&p| p != phase);
+			}
+			changed = true;
+		    }
+		}
+	    });
+	    ui.horizontal(|ui| {
+		ui.label("BOAT");
+		ComboBox::from_id_source("hazards_boat")
+		    .selected_text(hz.boat
+				   .and_then(|o| objects.iter().find(|ot| ot.object() == o))
+				   .map(|ot| ot.name.as_str())
+				   .unwrap_or("None"))
+		    .show_ui(ui,|ui| {
+			changed |= ui.selectable_value(&mut hz.boat,None,"None").changed();
+			for ot in objects.iter() {
+			    changed |= ui.selectable_value(&mut hz.boat,Some(ot.object()),&ot.name).changed();
+			}
+		    });
+		ui.label(format!("{} checkpoints",hz.checkpoints.len()));
+	    });
+	}
+	if changed {
+	    self.dirty = true;
+	}
+    }
+
+    /// A character free for a new object type: a letter that stands for
+    /// no tile and is not an editor key
+    fn free_object_code(&self)->Option<char> {
+	('a'..='z').chain('A'..='Z')
+	    .filter(|&c| ObjectType::is_free_code(c))
+	    .filter(|c| !"iDLUur".contains(*c))
+	    .find(|&c| self.tv.world.objects.iter().all(|ot| ot.code != c))
+    }
+
+    fn objects_ui(&mut self,ui:&mut Ui) {
+	ui.horizontal(|ui| {
+	    ui.label(format!("Objects: {}",self.tv.world.objects.len()));
+	    if ui.button("ADD OBJECT").clicked() {
+		match self.free_object_code() {
+		    None => self.message("No character left for a new object"),
+		    Some(c) => {
+			self.tv.world.objects.push(ObjectType::new(c,"A THING",(0,6)));
+			self.dirty = true;
+		    }
+		}
+	    }
+	});
+	const SOUNDS : &[Sounds] = &[Sounds::PickUpObject,Sounds::PickUpCoin,Sounds::EatFood,
+				     Sounds::Teleport,Sounds::Switch,Sounds::Splash];
+	let world = &mut self.tv.world;
+	let used : Vec<bool> = world.objects.iter().map(|ot| world.count_objects(ot.object()) > 0).collect();
+	let mut changed = false;
+	let mut delete = None;
+	for (i,ot) in world.objects.iter_mut().enumerate() {
+	    ui.horizontal(|ui| {
+		ui.monospace(ot.code.to_string());
+		changed |= ui.add(TextEdit::singleline(&mut ot.name).desired_width(120.0)).changed();
+		ui.label("SPRITE");
+		changed |= ui.add(DragValue::new(&mut ot.sprite.0).clamp_range(0..=15)).changed();
+		changed |= ui.add(DragValue::new(&mut ot.sprite.1).clamp_range(0..=15)).changed();
+		changed |= ui.checkbox(&mut ot.food,"FOOD").changed();
+		changed |= ui.checkbox(&mut ot.fattening,"FAT").changed();
+		changed |= ui.checkbox(&mut ot.coin,"COIN").changed();
+		changed |= ui.checkbox(&mut ot.consumable,"USED UP").changed();
+		changed |= ui.checkbox(&mut ot.key,"KEY").changed();
+		ComboBox::from_id_source(("object_sound",i))
+		    .selected_text(format!("{:?}",ot.sound))
+		    .show_ui(ui,|ui| {
+			for &snd in SOUNDS {
+			    changed |= ui.selectable_value(&mut ot.sound,snd,format!("{:?}",snd)).changed();
+			}
+		    });
+		ui.label("SCORE");
+		changed |= ui.add(DragValue::new(&mut ot.score).clamp_range(0..=9999)).changed();
+		if ui.add_enabled(!used[i],Button::new("DEL")).clicked() {
+		    delete = Some(i);
+		}
+	    });
+	}
+	if let Some(i) = delete {
+	    world.objects.remove(i);
+	    changed = true;
+	}
+	if changed {
+	    self.dirty = true;
+	}
+    }
+
+    fn tile_types_ui(&mut self,ui:&mut Ui) {
+	ui.horizontal(|ui| {
+	    ui.label(format!("Tile types: {}",self.tv.world.tile_types.len()));
+	    if ui.button("ADD TILE TYPE").clicked() {
+		let free = (' '..='~')
+		    .filter(|&c| TileType::is_free_code(c))
+		    .find(|&c| self.tv.world.tile_type(c).is_none());
+		match free {
+		    None => self.message("No character left for a new tile type"),
+		    Some(c) => {
+			self.tv.world.tile_types.push(TileType::new(c,"TERRAIN",(0,8)));
+			self.dirty = true;
+		    }
+		}
+	    }
+	});
+	const SOUNDS : &[Sounds] = &[Sounds::Walk,Sounds::Splash,Sounds::Hurt,Sounds::Switch,Sounds::Teleport];
+	let world = &mut self.tv.world;
+	let used : Vec<bool> = world.tile_types.iter().map(|tt| world.count_custom(tt.code) > 0).collect();
+	let mut changed = false;
+	let mut restart = Vec::new();
+	let mut delete = None;
+	for (i,tt) in world.tile_types.iter_mut().enumerate() {
+	    ui.horizontal(|ui| {
+		ui.monospace(tt.code.to_string());
+		changed |= ui.add(TextEdit::singleline(&mut tt.name).desired_width(120.0)).changed();
+		ui.label("SPRITE");
+		changed |= ui.add(DragValue::new(&mut tt.sprite.0).clamp_range(0..=15)).changed();
+		changed |= ui.add(DragValue::new(&mut tt.sprite.1).clamp_range(0..=15)).changed();
+		ui.label("FRAMES");
+		let frames = ui.add(DragValue::new(&mut tt.frames).clamp_range(1..=16)).changed();
+		ui.label("PERIOD");
+		let period = ui.add(DragValue::new(&mut tt.period).clamp_range(1..=60)).changed();
+		if frames || period {
+		    restart.push(tt.code);
+		    changed = true;
+		}
+		changed |= ui.checkbox(&mut tt.passable,"PASSABLE").changed();
+		ui.label("DAMAGE");
+		changed |= ui.add(DragValue::new(&mut tt.damage).clamp_range(0..=99)).changed();
+		ComboBox::from_id_source(("tile_sound",i))
+		    .selected_text(match tt.sound {
+			None => "SILENT".to_string(),
+			Some(snd) => format!("{:?}",snd)
+		    })
+		    .show_ui(ui,|ui| {
+			changed |= ui.selectable_value(&mut tt.sound,None,"SILENT").changed();
+			for &snd in SOUNDS {
+			    changed |= ui.selectable_value(&mut tt.sound,Some(snd),format!("{:?}",snd)).changed();
+			}
+		    });
+		if ui.add_enabled(!used[i],Button::new("DEL")).clicked() {
+		    delete = Some(i);
+		}
+	    });
+	}
+	for code in restart {
+	    world.reset_custom(code);
+	}
+	if let Some(i) = delete {
+	    world.tile_types.remove(i);
+	    changed = true;
+	}
+	if changed {
+	    self.dirty = true;
+	}
+    }
+
+    /// Add a creature at the GREEN selection, or remove the one there
+    fn creature(&mut self) {
+	match self.tv.selection1() {
+	    None => self.message("Select a tile in GREEN"),
+	    Some(ta) => {
+		match self.tv.world.creature_at(&ta) {
+		    Some(i) => {
+			let c = self.tv.world.creatures.remove(i);
+			self.message(&format!("Creature {} removed",c.name));
+		    },
+		    None => {
+			self.tv.world.creatures.push(Creature::new("ALIEN",ta.room_id,(ta.iy,ta.ix)));
+			self.message("Creature added");
+		    }
+		}
+		self.dirty = true;
+	    }
+	}
+    }
+
+    fn creatures_ui(&mut self,ui:&mut Ui) {
+	ui.label(format!("Creatures: {}",self.tv.world.creatures.len()));
+	let green = self.tv.selection1();
+	let mut changed = false;
+	let mut delete = None;
+	for (i,c) in self.tv.world.creatures.iter_mut().enumerate() {
+	    ui.horizontal(|ui| {
+		ui.label(format!("ROOM {} ({},{})",c.room,c.home.0,c.home.1));
+		changed |= ui.add(TextEdit::singleline(&mut c.name).desired_width(80.0)).changed();
+		let kind =
+		    match c.behaviour {
+			Behaviour::Still => "STILL",
+			Behaviour::Patrol(_) => "PATROL",
+			Behaviour::Wander => "WANDER",
+			Behaviour::Chase => "CHASE"
+		    };
+		ComboBox::from_id_source(("creature",i))
+		    .selected_text(kind)
+		    .show_ui(ui,|ui| {
+			for (label,b) in [("STILL",Behaviour::Still),
+					  ("PATROL",Behaviour::Patrol(vec![c.home])),
+					  ("WANDER",Behaviour::Wander),
+					  ("CHASE",Behaviour::Chase)] {
+			    if ui.selectable_label(kind == label,label).clicked() && kind != label {
+				c.behaviour = b;
+				changed = true;
+			    }
+			}
+		    });
+		if let Behaviour::Patrol(path) = &mut c.behaviour {
+		    ui.label(format!("{} POINTS",path.len()));
+		    if let Some(ta) = green.filter(|ta| ta.room_id == c.room) {
+			if ui.button("+GREEN").clicked() {
+			    path.push((ta.iy,ta.ix));
+			    changed = true;
+			}
+		    }
+		    if ui.button("CLEAR PATH").clicked() {
+			path.clear();
+			changed = true;
+		    }
+		}
+		ui.label("SPEED");
+		changed |= ui.add(DragValue::new(&mut c.speed).clamp_range(1..=120)).changed();
+		let contact =
+		    match c.contact {
+			Contact::Hurt(_) => "HURT",
+			Contact::Say(_) => "SAY",
+			Contact::Talk(_) => "TALK"
+		    };
+		ComboBox::from_id_source(("contact",i))
+		    .selected_text(contact)
+		    .show_ui(ui,|ui| {
+			for (label,k) in [("HURT",Contact::Hurt(1)),
+					  ("SAY",Contact::Say("HELLO".to_string())),
+					  ("TALK",Contact::Talk(Dialogue::default()))] {
+			    if ui.selectable_label(contact == label,label).clicked() && contact != label {
+				c.contact = k;
+				changed = true;
+			    }
+			}
+		    });
+		match &mut c.contact {
+		    Contact::Hurt(damage) =>
+			changed |= ui.add(DragValue::new(damage).clamp_range(1..=99)).changed(),
+		    Contact::Say(text) =>
+			changed |= ui.add(TextEdit::singleline(text).desired_width(160.0)).changed(),
+		    Contact::Talk(d) => { ui.label(format!("{} PAGES",d.pages.len())); }
+		}
+		if ui.button("DEL").clicked() {
+		    delete = Some(i);
+		}
+	    });
+	}
+	if let Some(i) = delete {
+	    self.tv.world.creatures.remove(i);
+	    changed = true;
+	}
+	if changed {
+	    self.dirty = true;
+	}
+    }
+
+    /// Start editing the text of the sign or creature at the GREEN
+    /// selection
+    fn edit_dialogue(&mut self) {
+	let ta =
+	    match self.tv.selection1() {
+		None => return self.message("Select a sign or creature in GREEN"),
+		Some(ta) => ta
+	    };
+	let world = &self.tv.world;
+	let found =
+	    if let Some(i) = world.creature_at(&ta) {
+		let text =
+		    match &world.creatures[i].contact {
+			Contact::Talk(d) => d.to_string(),
+			_ => String::new()
+		    };
+		Some((Speaker::Creature(i),text))
+	    } else if world.get_tile(&ta) == Some(Tile::Sign) {
+		Some((Speaker::Sign(ta),world.sign_at(&ta).map(|d| d.to_string()).unwrap_or_default()))
+	    } else {
+		None
+	    };
+	match found {
+	    None => self.message("No sign or creature at the GREEN selection"),
+	    Some(dt) => self.dialogue_text = Some(dt)
+	}
+    }
+
+    fn dialogue_ui(&mut self,ui:&mut Ui) {
+	let mut apply = false;
+	match self.dialogue_text.as_mut() {
+	    None => {
+		ui.label("Dialogue: select a sign or creature in GREEN and press TEXT");
+		return;
+	    },
+	    Some((speaker,text)) => {
+		match *speaker {
+		    Speaker::Sign(ta) => ui.label(format!("Dialogue of the sign at ({},{}) in room {}",
+							  ta.iy,ta.ix,ta.room_id)),
+		    Speaker::Creature(i) => ui.label(format!("Dialogue of creature {}",i))
+		};
+		ui.label("Pages are separated by --- lines, choices are lines like > YES -> 2 or > NO -> END");
+		ui.add(TextEdit::multiline(text).code_editor().desired_width(f32::INFINITY));
+		ui.horizontal(|ui| {
+		    apply = ui.button("APPLY").clicked();
+		    if ui.button("CANCEL").clicked() {
+			self.dialogue_text = None;
+		    }
+		});
+	    }
+	}
+	if !apply {
+	    return;
+	}
+	let (speaker,text) = self.dialogue_text.clone().unwrap();
+	let dialogue =
+	    match Dialogue::parse(&text) {
+		Ok(d) => d,
+		Err(e) => return self.message(&format!("Error: {}",e))
+	    };
+	let res =
+	    match speaker {
+		Speaker::Sign(ta) => self.tv.world.set_sign(ta,dialogue),
+		Speaker::Creature(i) =>
+		    match self.tv.world.creatures.get_mut(i) {
+			None => Err(anyhow::anyhow!("The creature is gone")),
+			Some(c) => {
+			    c.contact = Contact::Talk(dialogue);
+			    Ok(())
+			}
+		    }
+	    };
+	match res {
+	    Err(e) => self.message(&format!("Error: {}",e)),
+	    Ok(()) => {
+		self.message("Dialogue set");
+		self.dialogue_text = None;
+		self.dirty = true;
+	    }
+	}
+    }
+
+    fn scripts_ui(&mut self,ui:&mut Ui) {
+	let mut apply = false;
+	match self.scripts_text.as_mut() {
+	    None => {
+		ui.horizontal(|ui| {
+		    ui.label(format!("Scripts: {}",self.tv.world.scripts.len()));
+		    if ui.button("EDIT SCRIPTS").clicked() {
+			let lines : Vec<String> = self.tv.world.scripts.iter()
+			    .map(|s| ron::ser::to_string(s).unwrap_or_default())
+			    .collect();
+			self.scripts_text = Some(lines.join("\n"));
+		    }
+		});
+	    },
+	    Some(text) => {
+		ui.label("Scripts, one per line, e.g. (on:EnterRoom(2),once:true,actions:[Say(\"HELLO\")])");
+		ui.add(TextEdit::multiline(text).code_editor().desired_width(f32::INFINITY));
+		ui.horizontal(|ui| {
+		    apply = ui.button("APPLY").clicked();
+		    if ui.button("CANCEL").clicked() {
+			self.scripts_text = None;
+		    }
+		});
+	    }
+	}
+	if apply {
+	    let text = self.scripts_text.as_deref().unwrap_or("");
+	    let mut scripts = Vec::new();
+	    for (i,line) in text.lines().enumerate() {
+		if line.trim().is_empty() {
+		    continue;
+		}
+		match ron::de::from_str(line) {
+		    Ok(s) => scripts.push(s),
+		    Err(e) => {
+			self.message(&format!("Script line {}: {}",i + 1,e));
+			return;
+		    }
+		}
+	    }
+	    self.message(&format!("{} scripts",scripts.len()));
+	    self.tv.world.scripts = scripts;
+	    self.scripts_text = None;
+	    self.dirty = true;
+	}
+    }
+
+    /// Make the GREEN selection a checkpoint, or stop it being one
+    fn checkpoint(&mut self) {
+	match self.tv.selection1() {
+	    None => self.message("Select a tile in GREEN"),
+	    Some(ta) => {
+		let added = self.tv.world.hazards.toggle_checkpoint(ta);
+		self.dirty = true;
+		self.message(if added { "Checkpoint added" } else { "Checkpoint removed" });
+	    }
+	}
+    }
+
+    fn wire(&mut self) {
+	match self.tv.selection1().zip(self.tv.selection2()) {
+	    None => self.message("Select a lever, plate or coin slot in GREEN and a door or wall in RED"),
+	    Some((ta1,ta2)) => {
+		match self.tv.world.wire(ta1,ta2) {
+		    Err(e) => self.message(&format!("Error: {}",e)),
+		    Ok(connected) => {
+			self.dirty = true;
+			self.message(if connected { "Wire connected" } else { "Wire removed" });
+		    }
+		}
+	    }
+	}
+    }
+
     fn goto_room(&mut self,room_id:usize) {
 	if let Some(room) = self.tv.world.rooms.get(&room_id) {
 	    self.tv.set_room(Some(Ptr::clone(room)));
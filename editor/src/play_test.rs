@@ -0,0 +1,122 @@
+use crate::{
+    common::*,
+    goal::Status,
+    hero::Hero,
+    ptr::*,
+    refresher::Refresher,
+    room::Room,
+    world::{
+	World,
+	TileAddress
+    }
+};
+
+/// Keys selecting inventory slots
+const SLOT_KEYS : &[Key] = &[
+    Key::Num1,Key::Num2,Key::Num3,
+    Key::Num4,Key::Num5,Key::Num6,
+    Key::Num7,Key::Num8,Key::Num9
+];
+
+/// A play-test session running on a private copy of the edited world
+pub struct PlayTest {
+    world:World,
+    hero:Hero,
+    refresher:Refresher
+}
+
+impl PlayTest {
+    pub fn new(world:&World,start:TileAddress)->Self {
+	let mut world = world.deep_copy();
+	world.start = Some(start);
+	let hero = Hero::new(&world,"FELIX");
+	Self {
+	    world,
+	    hero,
+	    refresher:Refresher::new(1.0/60.0)
+	}
+    }
+
+    pub fn world(&self)->&World {
+	&self.world
+    }
+
+    pub fn hero(&self)->&Hero {
+	&self.hero
+    }
+
+    /// The goals of the world, marked as achieved, failed or pending
+    pub fn objectives(&self)->Vec<String> {
+	self.world.active_goals().iter()
+	    .map(|goal| {
+		let mark =
+		    match self.hero.goal_status(&self.world,goal) {
+			Status::Pending => ' ',
+			Status::Done => 'X',
+			Status::Failed => '!'
+		    };
+		format!("[{}] {}",mark,self.hero.objective(&self.world,goal))
+	    })
+	    .collect()
+    }
+
+    pub fn room(&self)->Option<Ptr<Room>> {
+	self.world.room(self.hero.room())
+    }
+
+    /// Feed keyboard events to the hero
+    pub fn input(&mut self,ui:&mut Ui) {
+	let events = ui.input(|i| i.events.clone());
+	for event in &events {
+	    if let &Event::Key { key,pressed,repeat,modifiers, .. } = event {
+		let deltas =
+		    match key {
+			Key::ArrowLeft => Some((0,-1)),
+			Key::ArrowRight => Some((0,1)),
+			Key::ArrowUp => Some((-1,0)),
+			Key::ArrowDown => Some((1,0)),
+			_ => None
+		    };
+		if self.hero.talking() && pressed {
+		    if !repeat {
+			match key {
+			    Key::ArrowUp => self.hero.choose(-1),
+			    Key::ArrowDown => self.hero.choose(1),
+			    Key::Space | Key::Enter => self.hero.advance(),
+			    _ => ()
+			}
+		    }
+		    continue;
+		}
+		match (deltas,pressed,repeat) {
+		    (Some((di,dj)),true,false) => self.hero.start(di,dj),
+		    (Some((di,dj)),false,_) => self.hero.stop(di,dj),
+		    (None,true,false) =>
+			match key {
+			    Key::Space => self.hero.drop(&mut self.world),
+			    Key::Tab => self.hero.cycle(&self.world,if modifiers.shift { -1 } else { 1 }),
+			    _ =>
+				if let Some(i) = SLOT_KEYS.iter().position(|&k| k == key) {
+				    self.hero.select(&self.world,i);
+				}
+			},
+		    _ => ()
+		}
+	    }
+	}
+    }
+
+    /// Advance the game by one frame if it is time to do so.  Returns
+    /// true if it did.
+    pub fn tick(&mut self,ui:&mut Ui)->bool {
+	if !self.refresher.tick(ui) {
+	    return false;
+	}
+	self.hero.tick(&mut self.world);
+	while self.hero.next_sound().is_some() { }
+	if let Some(room_ptr) = self.room() {
+	    room_ptr.yank_mut().next();
+	}
+	true
+    }
+}
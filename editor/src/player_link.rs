@@ -0,0 +1,140 @@
+use std::{
+    net::{
+	SocketAddr,
+	TcpStream,
+	ToSocketAddrs
+    },
+    process::{
+	Child,
+	Command as Process
+    },
+    time::Instant
+};
+
+use anyhow::{
+    anyhow,
+    bail
+};
+
+use crate::{
+    common::*,
+    link::{
+	self,
+	Command
+    }
+};
+
+/// Connection to a running player, which is launched if needed.
+/// Commands sent while the player is starting up are queued and
+/// delivered once it accepts the connection.
+pub struct PlayerLink {
+    stream:Option<TcpStream>,
+    child:Option<Child>,
+    pending:Vec<Command>,
+    last_attempt:Option<Instant>
+}
+
+impl PlayerLink {
+    const RETRY_PERIOD : Duration = Duration::from_millis(250);
+
+    pub fn new()->Self {
+	Self {
+	    stream:None,
+	    child:None,
+	    pending:Vec::new(),
+	    last_attempt:None
+	}
+    }
+
+    pub fn is_connected(&self)->bool {
+	self.stream.is_some()
+    }
+
+    pub fn is_pending(&self)->bool {
+	!self.pending.is_empty()
+    }
+
+    fn address()->Result<SocketAddr> {
+	link::address()
+	    .to_socket_addrs()?
+	    .next()
+	    .ok_or_else(|| anyhow!("Bad link address {}",link::address()))
+    }
+
+    /// Try to connect to the player, once
+    pub fn connect(&mut self)->bool {
+	if self.stream.is_none() {
+	    self.last_attempt = Some(Instant::now());
+	    if let Ok(addr) = Self::address() {
+		self.stream = TcpStream::connect_timeout(&addr,Duration::from_millis(100)).ok();
+	    }
+	}
+	self.stream.is_some()
+    }
+
+    /// Launch the player on the given world file
+    pub fn launch(&mut self,path:&Path)->Result<()> {
+	if self.child.is_some() {
+	    return Ok(());
+	}
+	let cmd = std::env::var("PLAYER_COMMAND")
+	    .map_err(|_| anyhow!("No PLAYER_COMMAND environment variable"))?;
+	let child = Process::new(cmd)
+	    .arg(path)
+	    .spawn()
+	    .map_err(|e| anyhow!("Could not launch player: {}",e))?;
+	self.child = Some(child);
+	Ok(())
+    }
+
+    /// Queue a command, replacing any older world that has not been
+    /// delivered yet
+    pub fn queue(&mut self,cmd:Command) {
+	if let Command::World(_) = cmd {
+	    self.pending.retain(|c| !matches!(c,Command::World(_)));
+	}
+	self.pending.push(cmd);
+    }
+
+    /// Drop undelivered commands
+    pub fn cancel(&mut self) {
+	self.pending.clear();
+    }
+
+    pub fn send(&mut self,cmd:Command)->Result<()> {
+	self.queue(cmd);
+	self.flush()
+    }
+
+    fn flush(&mut self)->Result<()> {
+	if !self.connect() {
+	    bail!("Player not running");
+	}
+	if let Some(stream) = self.stream.as_mut() {
+	    for cmd in self.pending.iter() {
+		if let Err(e) = cmd.send(stream) {
+		    self.stream = None;
+		    return Err(e);
+		}
+	    }
+	}
+	self.pending.clear();
+	Ok(())
+    }
+
+    /// To be called regularly; delivers queued commands and notices
+    /// when the player exits
+    pub fn poll(&mut self) {
+	if let Some(child) = self.child.as_mut() {
+	    if let Ok(Some(_)) = child.try_wait() {
+		self.child = None;
+		self.stream = None;
+		self.pending.clear();
+	    }
+	}
+	if self.is_pending() &&
+	    self.last_attempt.map(|t| t.elapsed() >= Self::RETRY_PERIOD).unwrap_or(true) {
+		let _ = self.flush();
+	    }
+    }
+}
@@ -19,7 +19,15 @@ use crate::{
 	TileAddress
     },
     mini_rng::MiniRNG,
+    vortex::Destination,
     ptr::*,
+    facing::Facing,
+    position::Position,
+    creature::{
+	Behaviour,
+	Creature
+    },
+    play_test::PlayTest,
     refresher::Refresher,
     room::Room
 };
@@ -100,22 +108,38 @@ pub struct TileViewer {
     selection2:Option<TileAddress>,
     tool:Tool,
     info:String,
-    goto:Option<usize>,
+    door_edit:Option<TileAddress>,
     hover:Option<(usize,usize)>,
     last_edit:Option<(usize,usize)>,
     refresher:Refresher,
     rng:MiniRNG,
     undos:BTreeMap<usize,Undo>,
     target_tile:Option<(usize,usize)>,
+    play:Option<PlayTest>,
+    modified:bool,
 }
 
 #[derive(Copy,Clone)]
 enum TileAspect {
     FromImage((Vec2,Vec2)),
-    Solid(Color32)
+    Solid(Color32),
+    /// Filled with a first colour and framed with a second one
+    Framed(Color32,Color32),
+    /// Filled with a first colour, with an arrow of the second one
+    Arrow(Color32,Color32,Facing)
 }
 
 impl TileViewer {
+    const CRATE : Color32 = Color32::from_rgb(139, 90, 43);
+    const CRATE_EDGE : Color32 = Color32::from_rgb(205,133, 63);
+    const PLATE_EDGE : Color32 = Color32::from_rgb(160,160,160);
+    const LEVER_OFF : Color32 = Color32::from_rgb(200, 40, 40);
+    const LEVER_ON : Color32 = Color32::from_rgb( 40,200, 40);
+    const GOLD : Color32 = Color32::from_rgb(255,215,  0);
+    const PAPER : Color32 = Color32::from_rgb(240,230,200);
+    const ICE : Color32 = Color32::from_rgb(180,220,255);
+    const BELT : Color32 = Color32::from_rgb( 80, 80, 80);
+
     const RAINBOW : &'static [Color32] = &[
 	Color32::from_rgb(255,  0,  0),
 	Color32::from_rgb(255,255,  0),
@@ -132,6 +156,31 @@ impl TileViewer {
 	self.info.clear();
     }
 
+    /// Drop the undo history and selections of a deleted room
+    pub fn forget_room(&mut self,id:usize) {
+	self.undos.remove(&id);
+	for sel in [&mut self.selection1,&mut self.selection2] {
+	    if sel.map(|ta| ta.room_id) == Some(id) {
+		*sel = None;
+	    }
+	}
+	if self.room.as_ref().map(|p| p.yank().id) == Some(id) {
+	    self.set_room(None);
+	}
+    }
+
+    /// Follow a renumbering of the rooms of the world
+    pub fn remap_rooms(&mut self,remap:&BTreeMap<usize,usize>) {
+	let f = |id:usize| remap.get(&id).copied().unwrap_or(id);
+	let undos = std::mem::take(&mut self.undos);
+	self.undos = undos.into_iter().map(|(id,undo)| (f(id),undo)).collect();
+	for sel in [&mut self.selection1,&mut self.selection2] {
+	    if let Some(ta) = sel.as_mut() {
+		ta.room_id = f(ta.room_id);
+	    }
+	}
+    }
+
     pub fn room(&self)->Option<Ptr<Room>> {
 	self.room.as_ref().map(Ptr::refer)
     }
@@ -160,13 +209,15 @@ impl TileViewer {
 	       selection2:None,
 	       tool:Tool::Nothing,
 	       info:String::new(),
-	       goto:None,
+	       door_edit:None,
 	       hover:None,
 	       last_edit:None,
 	       refresher:Refresher::new(0.05),
 	       rng:MiniRNG::new(1),
 	       undos:BTreeMap::new(),
-	       target_tile:None
+	       target_tile:None,
+	       play:None,
+	       modified:false
 	}
     }
 
@@ -198,6 +249,10 @@ impl TileViewer {
     fn hero()->TileAspect {
 	Self::image(4,0)
     }
+
+    fn creature()->TileAspect {
+	Self::image(0,14)
+    }
     
     fn find_tile(&self,tl:Tile)->TileAspect {
 	let fill = TileAspect::Solid;
@@ -213,16 +268,13 @@ impl TileViewer {
 	    Tile::MetalRamp(Corner::SW) => tile(1,12),
 	    Tile::MetalRamp(Corner::SE) => tile(1,13),
 	    Tile::MetalFoot => tile(3,12),
-	    Tile::Object(o) => match o {
-		Object::Coin => tile(0,1),
-		Object::IceCream => tile(0,3),
-		Object::Key => tile(0,4),
-		Object::ToyCar => tile(0,5),
-		Object::SquaresAndTriangles => tile(0,6),
-		Object::Tomato => tile(1,5),
-		Object::Eggplant => tile(1,6),
-		Object::Banana => tile(1,7),
-		Object::Carrot => tile(1,8)
+	    Tile::Custom { code,phase } => {
+		let (u,v) = self.world.tile_kind(code).sprite_at(phase);
+		tile(u as u16,v as u16)
+	    },
+	    Tile::Object(o) => {
+		let (u,v) = self.world.object_kind(o).sprite;
+		tile(u as u16,v as u16)
 	    },
 	    Tile::Door(Door{ target:None, .. }) => tile(2,3),
 	    Tile::Door(Door{ key:None, locked:false, .. }) => tile(0,2),
@@ -237,6 +289,17 @@ impl TileViewer {
 	    Tile::Water(p) => tile(p.i,9),
 	    Tile::Empty => fill(Color32::BLACK),
 	    Tile::Rainbow => fill(Self::RAINBOW[self.rainbow_index]),
+	    Tile::Crate { on_plate:false } => TileAspect::Framed(Self::CRATE,Self::CRATE_EDGE),
+	    Tile::Crate { on_plate:true } => TileAspect::Framed(Self::CRATE,Self::PLATE_EDGE),
+	    Tile::Plate => TileAspect::Framed(Color32::BLACK,Self::PLATE_EDGE),
+	    Tile::Lever { on:false } => TileAspect::Framed(Self::PLATE_EDGE,Self::LEVER_OFF),
+	    Tile::Lever { on:true } => TileAspect::Framed(Self::PLATE_EDGE,Self::LEVER_ON),
+	    Tile::CoinSlot { paid:false } => TileAspect::Framed(Color32::BLACK,Self::GOLD),
+	    Tile::CoinSlot { paid:true } => TileAspect::Framed(Self::GOLD,Self::GOLD),
+	    Tile::Sign => TileAspect::Framed(Self::PAPER,Self::CRATE),
+	    Tile::Ice => fill(Self::ICE),
+	    Tile::Conveyor(f) => TileAspect::Arrow(Self::BELT,Self::GOLD,f),
+	    Tile::OneWay(f) => TileAspect::Arrow(Color32::BLACK,Color32::WHITE,f),
 	}
     }
 
@@ -265,8 +328,8 @@ impl TileViewer {
 	self.info.push_str(u);
     }
 
-    pub fn take_goto(&mut self)->Option<usize> {
-	self.goto.take()
+    pub fn take_door_edit(&mut self)->Option<TileAddress> {
+	self.door_edit.take()
     }
 
     fn edit(&mut self,room:&mut RefMut<'_,Room>,iy:usize,ix:usize,tile:Tile) {
@@ -275,6 +338,7 @@ impl TileViewer {
 	let new = room.modify(iy,ix,tile);
 	let edit = Edit { iy,ix,old,new };
 	undo.edit(edit);
+	self.modified = true;
 	self.last_edit = Some((iy,ix));
 	self.hover = None;
     }
@@ -285,6 +349,7 @@ impl TileViewer {
 	    let undo = self.undos.entry(room.id).or_insert_with(Undo::new);
 	    if let Some(edit) = undo.undo() {
 		room.modify(edit.iy,edit.ix,edit.old);
+		self.modified = true;
 	    }
 	} else {
 	    self.info("No room");
@@ -297,6 +362,7 @@ impl TileViewer {
 	    let undo = self.undos.entry(room.id).or_insert_with(Undo::new);
 	    if let Some(edit) = undo.redo() {
 		room.modify(edit.iy,edit.ix,edit.new);
+		self.modified = true;
 	    }
 	} else {
 	    self.info("No room");
@@ -304,7 +370,11 @@ impl TileViewer {
     }
 
     fn draw_tile(&mut self,ui:&mut Ui,p0:Pos2,iy:isize,ix:isize,ta:TileAspect) {
-	let p1 = p0 + vec2(ix as f32,iy as f32)*self.tile_size;
+	self.draw_tile_at(ui,p0,iy as f32,ix as f32,ta);
+    }
+
+    fn draw_tile_at(&mut self,ui:&mut Ui,p0:Pos2,y:f32,x:f32,ta:TileAspect) {
+	let p1 = p0 + vec2(x,y)*self.tile_size;
 	let p2 = p1 + self.tile_size;
 	let rect = Rect::from_points(&[p1,p2]);
 	match ta {
@@ -316,6 +386,27 @@ impl TileViewer {
 		    Stroke::NONE
 		);
 	    },
+	    TileAspect::Framed(color,edge) => {
+		let w = self.tile_size.x/8.0;
+		ui.painter().rect(
+		    rect.shrink(w/2.0),
+		    0.0,
+		    color,
+		    Stroke::new(w,edge)
+		);
+	    },
+	    TileAspect::Arrow(color,arrow,f) => {
+		ui.painter().rect(rect,0.0,color,Stroke::NONE);
+		let (di,dj) = f.to_deltas();
+		let d = vec2(dj as f32,di as f32)*self.tile_size*0.3;
+		let n = vec2(-d.y,d.x);
+		let c = rect.center();
+		ui.painter().add(egui::Shape::convex_polygon(
+		    vec![c + d,c - d + n,c - d - n],
+		    arrow,
+		    Stroke::NONE
+		));
+	    },
 	    TileAspect::FromImage((q0,q1)) => {
 		if let Some(TexturePoll::Ready { texture })
 		    = self.img {
@@ -335,7 +426,147 @@ impl TileViewer {
 	}
     }
     
+    fn load_image(&mut self,ui:&mut Ui) {
+	self.img.get_or_insert_with(|| {
+	    include_image!("../../gfx/tiles.png")
+		.load(
+		    ui.ctx(),
+		    TextureOptions::NEAREST_MIRRORED_REPEAT,
+		    load::SizeHint::Size(320,320))
+		.expect("Can't load image")
+	});
+    }
+
+    /// Has the world been edited since the last call?
+    pub fn take_modified(&mut self)->bool {
+	std::mem::take(&mut self.modified)
+    }
+
+    pub fn is_playing(&self)->bool {
+	self.play.is_some()
+    }
+
+    /// Start a play-test on a copy of the world, with the hero at `start`
+    pub fn start_play(&mut self,start:TileAddress) {
+	self.play = Some(PlayTest::new(&self.world,start));
+	self.hover = None;
+	self.info.clear();
+    }
+
+    pub fn stop_play(&mut self) {
+	self.play = None;
+	self.info.clear();
+    }
+
+    fn do_play_ui(&mut self,ui:&mut Ui)->Response {
+	let (ny,nx) = (self.ny,self.nx);
+	let desired_size = vec2(nx as f32,ny as f32)*self.tile_size;
+	let (rect,response) =
+	    ui.allocate_exact_size(desired_size,Sense::click());
+
+	let play =
+	    match self.play.as_mut() {
+		None => return response,
+		Some(play) => play
+	    };
+	play.input(ui);
+	play.tick(ui);
+	let hero = play.hero();
+	let (position,fat,won) = (hero.position(),hero.is_fat(),hero.won());
+	let message = hero.message();
+	let mut dialogue = String::new();
+	if let Some(c) = hero.conversation() {
+	    write!(dialogue,"\n\n{}",c.page().text).unwrap();
+	    for (i,choice) in c.page().choices.iter().enumerate() {
+		write!(dialogue,"\n{} {}",if i == c.choice { ">" } else { " " },choice.text).unwrap();
+	    }
+	    dialogue.push_str("\n(SPACE TO GO ON)");
+	}
+	let inventory = hero.inventory();
+	let capacity = inventory.capacity();
+	let carrying : Vec<String> = inventory.items().iter().enumerate()
+	    .map(|(i,o)|
+		 if i == inventory.selected_index() {
+		     format!("[{}]",play.world().object_kind(*o).name)
+		 } else {
+		     play.world().object_kind(*o).name
+		 })
+	    .collect();
+	let objectives = play.objectives();
+	let health =
+	    if play.world().hazards.enabled {
+		match hero.lives() {
+		    None => format!("  HEALTH {}",hero.health()),
+		    Some(n) => format!("  HEALTH {} LIVES {}",hero.health(),n)
+		}
+	    } else {
+		String::new()
+	    };
+	let room_ptr = play.room();
+	let room_id = hero.room();
+	let creatures : Vec<(f32,f32)> = play.world().creatures.iter()
+	    .filter(|c| c.room == room_id)
+	    .map(|c| c.position().coords())
+	    .collect();
+
+	if self.refresher.tick(ui) {
+	    self.rainbow_index = (self.rainbow_index + 1).rem_euclid(Self::RAINBOW.len());
+	}
+
+	if !ui.is_rect_visible(rect) {
+	    return response;
+	}
+	ui.painter().rect(
+	    rect,
+	    0.0,
+	    Color32::DARK_GREEN,
+	    Stroke::NONE
+	);
+	self.load_image(ui);
+	let p0 = rect.left_top();
+
+	if let Some(room_ptr) = room_ptr {
+	    let room = room_ptr.yank();
+	    let (ny,nx) = room.map().dims();
+	    for iy in 0..ny {
+		for ix in 0..nx {
+		    self.draw_tile(ui,p0,iy,ix,self.find_tile(room.map()[[iy,ix]]));
+		}
+	    }
+	    self.info = format!("PLAYING in {}: {}{}\nCARRYING {}/{}: {}{}\nGOALS: {}{}",room.name,message,
+				if won { " (WON)" } else { "" },
+				carrying.len(),capacity,carrying.join(", "),health,
+				objectives.join("  "),dialogue);
+	}
+
+	let u = match position.facing() {
+	    Facing::Right => 1,
+	    Facing::Left => 2,
+	    Facing::Up => 3,
+	    Facing::Down => 4
+	};
+	let u = if fat { u + 5 } else { u };
+	let (y,x,v) =
+	    match position {
+		Position::Block(hi,hj,_) => (hi as f32,hj as f32,0),
+		Position::Walking{ from:(hi0,hj0),to:(hi1,hj1),step,total } => {
+		    let t = step as f32/total as f32;
+		    (hi0 as f32 + t*(hi1 as f32 - hi0 as f32),
+		     hj0 as f32 + t*(hj1 as f32 - hj0 as f32),
+		     1 + (step % 2) as u16)
+		}
+	    };
+	self.draw_tile_at(ui,p0,y,x,Self::image(u,v));
+	for (y,x) in creatures {
+	    self.draw_tile_at(ui,p0,y,x,Self::creature());
+	}
+	response
+    }
+
     pub fn do_ui(&mut self,ui:&mut Ui)->Response {
+	if self.play.is_some() {
+	    return self.do_play_ui(ui);
+	}
 	let (ny,nx) = (self.ny,self.nx);
 	let desired_size = vec2(nx as f32,ny as f32)*self.tile_size;
 	let (rect,response) =
@@ -363,14 +594,7 @@ impl TileViewer {
 		// let map = room.map();
 		let (ny,nx) = room.map().dims();
 
-		self.img.get_or_insert_with(|| {
-		    include_image!("../../gfx/tiles.png")
-			.load(
-			    ui.ctx(),
-			    TextureOptions::NEAREST_MIRRORED_REPEAT,
-			    load::SizeHint::Size(320,320))
-			.expect("Can't load image")
-		});
+		self.load_image(ui);
 
 		let p0 = rect.left_top();
 
@@ -383,6 +607,23 @@ impl TileViewer {
 			let mut info = String::new();
 			let tile = room.map()[[iy,ix]];
 			write!(info,"({:02},{:02}) {}",iy,ix,tile).unwrap();
+			match tile {
+			    Tile::Object(o) => write!(info,": {}",self.world.object_kind(o).name).unwrap(),
+			    Tile::Custom { code,.. } => write!(info,": {}",self.world.tile_kind(code).name).unwrap(),
+			    _ => ()
+			}
+			if self.world.hazards.is_checkpoint(&TileAddress { room_id,iy,ix }) {
+			    info.push_str(" (checkpoint)");
+			}
+			if let Some(t) = self.world.trigger_at(&TileAddress { room_id,iy,ix }) {
+			    write!(info," (wired to {} targets)",t.targets.len()).unwrap();
+			}
+			if self.world.is_switched(&TileAddress { room_id,iy,ix }) {
+			    info.push_str(" (switched)");
+			}
+			if let Some(i) = self.world.creature_at(&TileAddress { room_id,iy,ix }) {
+			    write!(info," (creature {})",self.world.creatures[i].name).unwrap();
+			}
 			self.info = info;
 			self.hover = hover;
 			self.target_tile = None;
@@ -392,6 +633,20 @@ impl TileViewer {
 				    self.target_tile = room.locate_door(door);
 				}
 			    },
+			    Tile::Vortex => {
+				let at = TileAddress { room_id,iy,ix };
+				match self.world.vortex_destination(&at) {
+				    None => self.info.push_str(" leading nowhere"),
+				    Some(Destination::Random) => self.info.push_str(" leading to a random vortex"),
+				    Some(Destination::Tile(ta)) => {
+					write!(self.info," leading to ({:02},{:02}) in room {}",
+					       ta.iy,ta.ix,ta.room_id).unwrap();
+					if ta.room_id == room_id {
+					    self.target_tile = Some((ta.iy,ta.ix));
+					}
+				    }
+				}
+			    },
 			    _ => ()
 			}
 		    }
@@ -406,10 +661,9 @@ impl TileViewer {
 					if Some((iy,ix)) != self.last_edit {
 					    match self.tool {
 						Tool::Nothing => {
-						    if let Tile::Door(
-							Door { target:Some(Target { room, .. }),
-							       .. }) = room.map()[[iy,ix]] {
-							self.goto = Some(room);
+						    if let Tile::Door(_) = room.map()[[iy,ix]] {
+							self.door_edit = Some(TileAddress { room_id,iy,ix });
+							self.last_edit = Some((iy,ix));
 						    }
 						},
 						Tool::Place(tile) => {
@@ -517,6 +771,25 @@ impl TileViewer {
 		    }
 		}
 
+		let creatures : Vec<Creature> = self.world.creatures.iter()
+		    .filter(|c| c.room == room_id)
+		    .cloned()
+		    .collect();
+		for creature in creatures.iter() {
+		    let (iy,ix) = creature.home;
+		    self.draw_tile(ui,p0,iy as isize,ix as isize,Self::creature());
+		    if let Behaviour::Patrol(path) = &creature.behaviour {
+			for (k,&(iy,ix)) in path.iter().enumerate() {
+			    ui.painter().text(
+				self.tile_rect(p0,iy,ix,0.0).center(),
+				Align2::CENTER_CENTER,
+				format!("{}",k + 1),
+				FontId::monospace(self.tile_size.y/2.0),
+				Color32::LIGHT_RED);
+			}
+		    }
+		}
+
 		match self.target_tile {
 		    Some((iy,ix)) => {
 			ui.painter().rect_stroke(
@@ -32,6 +32,13 @@ impl<T> A2<T> where T:Copy+Serialize+for <'a> Deserialize<'a> {
 	(self.d0,self.d1)
     }
 
+    /// Do the dimensions match the data?  Not necessarily the case
+    /// for arrays deserialized from untrusted sources.
+    pub fn is_consistent(&self)->bool {
+	self.d0 >= 0 && self.d1 >= 0 &&
+	    self.d0.checked_mul(self.d1) == Some(self.data.len() as isize)
+    }
+
     pub fn as_slice(&self)->&[T] {
 	&self.data
     }
@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    dialogue::Dialogue,
+    facing::Facing,
+    goal::TICKS_PER_SECOND,
+    mini_rng::MiniRNG,
+    position::Position,
+    room::Room,
+    tiles::Tile
+};
+
+/// How a creature moves
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Behaviour {
+    /// Stays where it was put
+    Still,
+    /// Walks to each tile of the path in turn, forever
+    Patrol(Vec<(usize,usize)>),
+    /// Walks in random directions
+    Wander,
+    /// Walks towards the hero when in the same room
+    Chase
+}
+
+/// What happens when a creature and the hero meet
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Contact {
+    /// The hero loses health, if hazards are enabled
+    Hurt(usize),
+    /// The creature says something
+    Say(String),
+    /// The creature starts a dialogue
+    Talk(Dialogue)
+}
+
+/// Something moving around a room, drawn over the map with the alien
+/// sprite
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct Creature {
+    pub name:String,
+    pub room:usize,
+    /// Where the creature starts
+    pub home:(usize,usize),
+    pub behaviour:Behaviour,
+    pub contact:Contact,
+    /// Ticks per step
+    #[serde(default = "default_speed")]
+    pub speed:usize,
+    #[serde(skip)]
+    position:Option<Position>,
+    /// Index of the next point of the patrol path
+    #[serde(skip)]
+    leg:usize,
+    /// Ticks before the creature can meet the hero again
+    #[serde(skip)]
+    rest:usize
+}
+
+fn default_speed()->usize {
+    Creature::DEFAULT_SPEED
+}
+
+const DIRECTIONS : [Facing;4] = [Facing::Up,Facing::Down,Facing::Left,Facing::Right];
+
+/// The tile next to `(iy,ix)` in direction `f`, if in the room
+fn neighbour(room:&Room,(iy,ix):(usize,usize),f:Facing)->Option<(usize,usize)> {
+    let (di,dj) = f.to_deltas();
+    let (i,j) = (iy as isize + di,ix as isize + dj);
+    if 0 <= i && i < room.rows as isize && 0 <= j && j < room.cols as isize {
+	Some((i as usize,j as usize))
+    } else {
+	None
+    }
+}
+
+fn inside(room:&Room,(iy,ix):(usize,usize))->bool {
+    iy < room.rows && ix < room.cols
+}
+
+/// The first step of a shortest path from `from` to `to` going
+/// through tiles satisfying `free`
+pub fn first_step<F:Fn((usize,usize))->bool>(room:&Room,from:(usize,usize),to:(usize,usize),
+					       free:F)->Option<(usize,usize)> {
+    if from == to || !inside(room,from) || !inside(room,to) || !free(to) {
+	return None;
+    }
+    // Search from the destination, so that the tile reached just
+    // before the origin is the first step
+    let mut seen = vec![false;room.rows*room.cols];
+    let mut queue = VecDeque::new();
+    seen[to.0*room.cols + to.1] = true;
+    queue.push_back(to);
+    while let Some(p) = queue.pop_front() {
+	for f in DIRECTIONS {
+	    if let Some(q) = neighbour(room,p,f) {
+		if q == from {
+		    return Some(p);
+		}
+		if !seen[q.0*room.cols + q.1] && free(q) {
+		    seen[q.0*room.cols + q.1] = true;
+		    queue.push_back(q);
+		}
+	    }
+	}
+    }
+    None
+}
+
+impl Creature {
+    pub const DEFAULT_SPEED : usize = 16;
+
+    pub fn new(name:&str,room:usize,home:(usize,usize))->Self {
+	Self {
+	    name:name.to_string(),
+	    room,
+	    home,
+	    behaviour:Behaviour::Still,
+	    contact:Contact::Hurt(1),
+	    speed:Self::DEFAULT_SPEED,
+	    position:None,
+	    leg:0,
+	    rest:0
+	}
+    }
+
+    pub fn position(&self)->Position {
+	self.position.unwrap_or(Position::Block(self.home.0,self.home.1,Facing::Down))
+    }
+
+    /// The tile the creature stands on, or is stepping onto
+    pub fn tile(&self)->(usize,usize) {
+	match self.position() {
+	    Position::Block(iy,ix,_) => (iy,ix),
+	    Position::Walking{ to, .. } => to
+	}
+    }
+
+    /// The tiles the creature must be able to reach: its home and the
+    /// points of its patrol path
+    pub fn points(&self)->Vec<(usize,usize)> {
+	let mut points = vec![self.home];
+	if let Behaviour::Patrol(path) = &self.behaviour {
+	    points.extend(path.iter().copied());
+	}
+	points
+    }
+
+    /// Put the creature back home
+    pub fn reset(&mut self) {
+	self.position = None;
+	self.leg = 0;
+	self.rest = 0;
+    }
+
+    /// Can a creature walk on a tile?
+    pub fn can_walk_on(tile:Tile)->bool {
+	matches!(tile,Tile::Empty | Tile::Plate)
+    }
+
+    /// Advance by one tick in `room`, where the hero stands at `hero`
+    /// and other creatures occupy the tiles of `blocked`
+    pub fn tick(&mut self,room:&Room,hero:(usize,usize),blocked:&[(usize,usize)],rng:&mut MiniRNG) {
+	if self.rest > 0 {
+	    self.rest -= 1;
+	}
+	match self.position() {
+	    Position::Walking{ from,to,step,total } => {
+		self.position =
+		    if step + 1 >= total {
+			let f = Facing::from_deltas(to.0 as isize - from.0 as isize,
+						    to.1 as isize - from.1 as isize);
+			Some(Position::Block(to.0,to.1,f))
+		    } else {
+			Some(Position::Walking{ from,to,step:step + 1,total })
+		    };
+	    },
+	    Position::Block(iy,ix,_) => {
+		if let Some(to) = self.choose(room,(iy,ix),hero,blocked,rng) {
+		    self.position = Some(Position::Walking{ from:(iy,ix),to,step:0,total:self.speed.max(1) });
+		}
+	    }
+	}
+    }
+
+    fn choose(&mut self,room:&Room,at:(usize,usize),hero:(usize,usize),
+	      blocked:&[(usize,usize)],rng:&mut MiniRNG)->Option<(usize,usize)> {
+	if !inside(room,at) {
+	    return None;
+	}
+	let free = |t:(usize,usize)| Self::can_walk_on(room.map[[t.0,t.1]]) &&
+	    !blocked.contains(&t) && t != hero;
+	match &self.behaviour {
+	    Behaviour::Still => None,
+	    Behaviour::Wander => {
+		// Stand still half of the time
+		let k = rng.sample_u32(8) as usize;
+		DIRECTIONS.get(k)
+		    .and_then(|&f| neighbour(room,at,f))
+		    .filter(|&t| free(t))
+	    },
+	    Behaviour::Patrol(path) => {
+		if path.is_empty() {
+		    return None;
+		}
+		// Skip the points reached and those outside the room
+		self.leg %= path.len();
+		for _ in 0..path.len() {
+		    if path[self.leg] != at && inside(room,path[self.leg]) {
+			return first_step(room,at,path[self.leg],free);
+		    }
+		    self.leg = (self.leg + 1) % path.len();
+		}
+		None
+	    },
+	    Behaviour::Chase => first_step(room,at,hero,|t| t == hero || free(t))
+	}
+    }
+
+    /// Does the creature meet the hero standing at `hero`?  If so it
+    /// cannot do so again for a second.
+    pub fn meets(&mut self,hero:(usize,usize))->bool {
+	if self.rest == 0 && self.tile() == hero {
+	    self.rest = TICKS_PER_SECOND;
+	    true
+	} else {
+	    false
+	}
+    }
+
+    /// Make the creature wait before meeting the hero again
+    pub fn calm(&mut self) {
+	self.rest = TICKS_PER_SECOND;
+    }
+}
+
+#[test]
+fn test_first_step() {
+    let (room,_) = Room::parse(1,"R",&[
+	"    ",
+	" ## ",
+	" #  "],None).unwrap();
+    let free = |t:(usize,usize)| room.map[[t.0,t.1]] == Tile::Empty;
+    assert_eq!(first_step(&room,(2,0),(2,2),free),Some((1,0)));
+    assert_eq!(first_step(&room,(0,1),(2,3),free),Some((0,2)));
+    assert_eq!(first_step(&room,(0,0),(1,1),free),None);
+    assert_eq!(first_step(&room,(0,0),(5,5),free),None);
+
+    let mut cat = Creature::new("CAT",1,(0,0));
+    cat.behaviour = Behaviour::Patrol(vec![(9,9),(0,2)]);
+    let mut rng = MiniRNG::new(1);
+    assert_eq!(cat.choose(&room,(0,0),(2,3),&[],&mut rng),Some((0,1)));
+    assert_eq!(cat.choose(&room,(7,7),(2,3),&[],&mut rng),None);
+}
@@ -0,0 +1,188 @@
+use std::fmt::{
+    Display,
+    Formatter
+};
+
+use anyhow::{
+    anyhow,
+    bail,
+    Result
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::world::TileAddress;
+
+/// An answer offered at the end of a page
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Choice {
+    pub text:String,
+    /// Index of the page it leads to, or `None` to end the dialogue
+    pub goto:Option<usize>
+}
+
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Page {
+    pub text:String,
+    /// Without choices, the dialogue goes on to the next page
+    #[serde(default)]
+    pub choices:Vec<Choice>
+}
+
+/// Text shown page by page in a dialogue box.  It is authored in a
+/// plain format where pages are separated by `---` lines and choices
+/// are lines such as `> YES -> 2` or `> NO -> END`, pages being
+/// numbered from 1.
+#[derive(Clone,Debug,Default,PartialEq,Serialize,Deserialize)]
+pub struct Dialogue {
+    pub pages:Vec<Page>
+}
+
+/// The text of a sign tile
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Sign {
+    pub at:TileAddress,
+    pub dialogue:Dialogue
+}
+
+impl Dialogue {
+    pub fn parse(text:&str)->Result<Self> {
+	let mut pages = Vec::new();
+	let mut page = Page { text:String::new(),choices:Vec::new() };
+	for (i,line) in text.lines().enumerate() {
+	    if line.trim() == "---" {
+		pages.push(page);
+		page = Page { text:String::new(),choices:Vec::new() };
+	    } else if let Some(rest) = line.strip_prefix('>') {
+		let (text,goto) = rest.rsplit_once("->")
+		    .ok_or_else(|| anyhow!("Line {}: choice without destination",i + 1))?;
+		let goto =
+		    match goto.trim() {
+			"END" => None,
+			u => {
+			    let n : usize = u.parse()
+				.map_err(|_| anyhow!("Line {}: bad page number {:?}",i + 1,u))?;
+			    if n == 0 {
+				bail!("Line {}: pages are numbered from 1",i + 1);
+			    }
+			    Some(n - 1)
+			}
+		    };
+		page.choices.push(Choice { text:text.trim().to_string(),goto });
+	    } else if !page.choices.is_empty() {
+		bail!("Line {}: text after the choices of a page",i + 1);
+	    } else {
+		if !page.text.is_empty() {
+		    page.text.push('\n');
+		}
+		page.text.push_str(line);
+	    }
+	}
+	pages.push(page);
+	pages.retain(|p| !p.text.is_empty() || !p.choices.is_empty());
+	let this = Self { pages };
+	for (i,p) in this.pages.iter().enumerate() {
+	    for c in p.choices.iter() {
+		if let Some(n) = c.goto {
+		    if n >= this.pages.len() {
+			bail!("Page {}: choice {:?} leads to missing page {}",i + 1,c.text,n + 1);
+		    }
+		}
+	    }
+	}
+	Ok(this)
+    }
+
+    pub fn is_empty(&self)->bool {
+	self.pages.is_empty()
+    }
+}
+
+impl Display for Dialogue {
+    fn fmt(&self,f:&mut Formatter<'_>)->std::result::Result<(),std::fmt::Error> {
+	for (i,p) in self.pages.iter().enumerate() {
+	    if i > 0 {
+		writeln!(f,"---")?;
+	    }
+	    if !p.text.is_empty() {
+		writeln!(f,"{}",p.text)?;
+	    }
+	    for c in p.choices.iter() {
+		match c.goto {
+		    None => writeln!(f,"> {} -> END",c.text)?,
+		    Some(n) => writeln!(f,"> {} -> {}",c.text,n + 1)?
+		}
+	    }
+	}
+	Ok(())
+    }
+}
+
+/// A dialogue being read
+#[derive(Clone,Debug)]
+pub struct Conversation {
+    pub dialogue:Dialogue,
+    pub page:usize,
+    /// Selected choice of the current page
+    pub choice:usize
+}
+
+impl Conversation {
+    pub fn new(dialogue:Dialogue)->Self {
+	Self { dialogue,page:0,choice:0 }
+    }
+
+    pub fn page(&self)->&Page {
+	&self.dialogue.pages[self.page]
+    }
+
+    /// Select another choice, if there are any
+    pub fn choose(&mut self,delta:isize) {
+	let n = self.page().choices.len() as isize;
+	if n > 0 {
+	    self.choice = (self.choice as isize + delta).rem_euclid(n) as usize;
+	}
+    }
+
+    /// Go on to the next page, or the one of the selected choice.
+    /// Returns false when the dialogue is over.
+    pub fn advance(&mut self)->bool {
+	let next =
+	    match self.page().choices.get(self.choice) {
+		Some(c) => c.goto,
+		None => Some(self.page + 1)
+	    };
+	match next {
+	    Some(n) if n < self.dialogue.pages.len() => {
+		self.page = n;
+		self.choice = 0;
+		true
+	    },
+	    _ => false
+	}
+    }
+}
+
+#[test]
+fn test_dialogue() {
+    let text = "HELLO TRAVELLER\nDO YOU WANT A HINT?\n> YES -> 2\n> NO -> END\n---\nPUSH THE CRATES\n";
+    let d = Dialogue::parse(text).unwrap();
+    assert_eq!(d.pages.len(),2);
+    assert_eq!(d.pages[0].text,"HELLO TRAVELLER\nDO YOU WANT A HINT?");
+    assert_eq!(d.pages[0].choices[0].goto,Some(1));
+    assert_eq!(d.to_string(),text);
+
+    let mut c = Conversation::new(d.clone());
+    c.choose(1);
+    assert!(!c.advance());
+    let mut c = Conversation::new(d);
+    assert!(c.advance());
+    assert_eq!(c.page().text,"PUSH THE CRATES");
+    assert!(!c.advance());
+
+    assert!(Dialogue::parse("A\n> B -> 3").is_err());
+    assert!(Dialogue::parse("A\n> B").is_err());
+}
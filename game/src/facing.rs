@@ -1,4 +1,9 @@
-#[derive(Copy,Clone,Debug,PartialEq)]
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+#[derive(Copy,Clone,Debug,PartialEq,Serialize,Deserialize)]
 pub enum Facing {
     Up,
     Down,
@@ -0,0 +1,103 @@
+use anyhow::{
+    anyhow,
+    bail,
+    Result
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    object::Object,
+    text::Message,
+    msg,
+    world::{
+	TileAddress,
+	World
+    }
+};
+
+/// Game ticks per second, in the player as in the editor.  Time limits
+/// are counted in ticks, so they run late if a frame takes longer.
+pub const TICKS_PER_SECOND : usize = 60;
+
+/// Something the hero must do to win a world.  A world is won when
+/// all of its goals are achieved.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Goal {
+    /// Walk into a rainbow
+    ReachRainbow,
+    /// Leave no coin in the world
+    CollectAllCoins,
+    /// Pick up `count` of an object, including those eaten or spent
+    Collect { object:Object,count:usize },
+    /// Have an object lying at a given tile
+    Deliver { object:Object,to:TileAddress },
+    /// Enter a room, within a number of seconds if given
+    ReachRoom { room:usize,within:Option<usize> },
+    /// Leave no pressure plate without a crate
+    CoverAllPlates
+}
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Status {
+    Pending,
+    Done,
+    Failed
+}
+
+impl Goal {
+    /// The goals of worlds that do not define any
+    pub const DEFAULT : &'static [Goal] = &[Goal::ReachRainbow];
+
+    /// Parse the words following `GOAL` in a `.wld` file
+    pub fn parse(words:&[&str])->Result<Self> {
+	let num = |u:&str| u.parse::<usize>().map_err(|_| anyhow!("Bad integer {}",u));
+	let obj = |u:&str| {
+	    let cs : Vec<char> = u.chars().collect();
+	    if cs.len() == 1 {
+		Object::from_char(cs[0])
+	    } else {
+		bail!("Invalid object string {:?}",u)
+	    }
+	};
+	let goal =
+	    match words {
+		["RAINBOW"] => Goal::ReachRainbow,
+		["COINS"] => Goal::CollectAllCoins,
+		["PLATES"] => Goal::CoverAllPlates,
+		["COLLECT",o,n] => Goal::Collect { object:obj(o)?,count:num(n)? },
+		["DELIVER",o,room,iy,ix] =>
+		    Goal::Deliver { object:obj(o)?,
+				    to:TileAddress { room_id:num(room)?,iy:num(iy)?,ix:num(ix)? } },
+		["ROOM",room] => Goal::ReachRoom { room:num(room)?,within:None },
+		["ROOM",room,secs] => Goal::ReachRoom { room:num(room)?,within:Some(num(secs)?) },
+		_ => bail!("Invalid goal {:?}",words.join(" "))
+	    };
+	Ok(goal)
+    }
+
+    /// Description of the goal for the player
+    pub fn message(&self,world:&World)->Message {
+	match self {
+	    Goal::ReachRainbow => msg!("goal.rainbow"),
+	    Goal::CollectAllCoins => msg!("goal.coins"),
+	    &Goal::Collect { object,count } => msg!("goal.collect",count,&world.object_kind(object)),
+	    &Goal::Deliver { object,to } => msg!("goal.deliver",&world.object_kind(object),to.room_id),
+	    &Goal::ReachRoom { room,within:None } => msg!("goal.room",room),
+	    &Goal::ReachRoom { room,within:Some(s) } => msg!("goal.room_within",room,s),
+	    Goal::CoverAllPlates => msg!("goal.plates")
+	}
+    }
+}
+
+#[test]
+fn test_parse_goal() {
+    assert_eq!(Goal::parse(&["COLLECT","K","2"]).unwrap(),
+	       Goal::Collect { object:Object::KEY,count:2 });
+    assert_eq!(Goal::parse(&["ROOM","3","60"]).unwrap(),
+	       Goal::ReachRoom { room:3,within:Some(60) });
+    assert!(Goal::parse(&["DELIVER","K","1"]).is_err());
+}
@@ -0,0 +1,64 @@
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    object::Object,
+    world::TileAddress
+};
+
+/// How fire and water treat the hero.  When disabled, as in older
+/// worlds, both behave as walls.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Hazards {
+    pub enabled:bool,
+    /// Health of the hero at the start of each life
+    pub health:usize,
+    /// Number of lives, unlimited if `None`
+    pub lives:Option<usize>,
+    /// Health lost when stepping into fire
+    pub fire_damage:usize,
+    /// Animation phases during which fire hurts; all of them if empty
+    pub fire_phases:Vec<u16>,
+    /// Object needed to cross water; water cannot be crossed if `None`
+    pub boat:Option<Object>,
+    /// Tiles where the hero comes back to life after dying there
+    pub checkpoints:Vec<TileAddress>
+}
+
+impl Default for Hazards {
+    fn default()->Self {
+	Self {
+	    enabled:false,
+	    health:3,
+	    lives:Some(3),
+	    fire_damage:1,
+	    fire_phases:Vec::new(),
+	    boat:None,
+	    checkpoints:Vec::new()
+	}
+    }
+}
+
+impl Hazards {
+    /// Does fire in animation phase `phase` hurt?
+    pub fn fire_hurts(&self,phase:u16)->bool {
+	self.fire_phases.is_empty() || self.fire_phases.contains(&phase)
+    }
+
+    pub fn is_checkpoint(&self,ta:&TileAddress)->bool {
+	self.checkpoints.contains(ta)
+    }
+
+    /// Add or remove a checkpoint.  Returns true if it was added.
+    pub fn toggle_checkpoint(&mut self,ta:TileAddress)->bool {
+	if self.is_checkpoint(&ta) {
+	    self.checkpoints.retain(|c| *c != ta);
+	    false
+	} else {
+	    self.checkpoints.push(ta);
+	    true
+	}
+    }
+}
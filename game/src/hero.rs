@@ -0,0 +1,1614 @@
+use std::collections::{
+    BTreeMap,
+    VecDeque
+};
+
+use anyhow::{
+    bail,
+    Result
+};
+
+use crate::{
+    position::Position,
+    facing::Facing,
+    goal::{
+	Goal,
+	Status,
+	TICKS_PER_SECOND
+    },
+    creature::Contact,
+    dialogue::{
+	Conversation,
+	Dialogue
+    },
+    room::Room,
+    script::{
+	Action,
+	Event,
+	Script
+    },
+    world::{TileAddress,World},
+    tiles::{Target,Door,Requirement,Tile},
+    inventory::Inventory,
+    mini_rng::MiniRNG,
+    vortex::Destination,
+    object::Object,
+    sounds::Sounds,
+    text::Message,
+    msg,
+    ptr::*
+};
+
+#[derive(Debug)]
+pub struct Hero {
+    room:usize,
+    position:Position,
+    inventory:Inventory,
+    travel_request:Option<(isize,isize)>,
+    name:String,
+    /// Messages said so far, the last one being shown
+    log:VecDeque<Message>,
+    fat:usize,
+    won:bool,
+    lost:bool,
+    coins:usize,
+    foods:usize,
+    sounds:VecDeque<Sounds>,
+    /// Ticks since the start of the game
+    ticks:usize,
+    /// Has the hero walked into a rainbow?
+    rainbow:bool,
+    /// Tick at which each room was first entered
+    entered:BTreeMap<usize,usize>,
+    /// Number of each object picked up so far
+    picked:Vec<(Object,usize)>,
+    /// Points scored by picking up objects
+    score:usize,
+    /// For vortices leading to random places
+    rng:MiniRNG,
+    health:usize,
+    /// Lives left, unlimited if `None`
+    lives:Option<usize>,
+    dead:bool,
+    /// Where the hero comes back to life
+    respawn:Option<TileAddress>,
+    /// Health to lose when the current step is over
+    pending_damage:usize,
+    /// What causes the pending damage, if not fire
+    pending_cause:Option<Message>,
+    /// Room the hero was in at the last tick
+    last_room:Option<usize>,
+    /// Events waiting for the world's scripts to run
+    events:VecDeque<Event>,
+    /// The scripts that have run, kept whole so that they are still
+    /// recognised in a new version of the world
+    scripts_run:Vec<Script>,
+    /// Dialogue being read, during which the hero stays put
+    conversation:Option<Conversation>
+}
+
+const FAT_PENALTY : usize = 256;
+
+impl Hero {
+    /// Number of messages kept in the log
+    pub const LOG_LENGTH : usize = 100;
+
+    pub fn position(&self)->Position { self.position }
+
+    pub fn inventory(&self)->&Inventory { &self.inventory }
+
+    pub fn room(&self)->usize { self.room }
+
+    pub fn foods(&self)->usize { self.foods }
+
+    pub fn coins(&self)->usize { self.coins }
+
+    pub fn score(&self)->usize { self.score }
+    
+    pub fn won(&self)->bool { self.won }
+
+    /// Has a goal become impossible to achieve?
+    pub fn lost(&self)->bool { self.lost }
+
+    pub fn ticks(&self)->usize { self.ticks }
+
+    pub fn health(&self)->usize { self.health }
+
+    pub fn lives(&self)->Option<usize> { self.lives }
+
+    /// Has the hero lost its last life?
+    pub fn dead(&self)->bool { self.dead }
+
+    /// The last message, in English
+    pub fn message(&self)->String {
+	self.log.back().map(|m| m.to_string()).unwrap_or_default()
+    }
+
+    /// The messages said so far, oldest first
+    pub fn log(&self)->&VecDeque<Message> { &self.log }
+
+    pub fn conversation(&self)->Option<&Conversation> { self.conversation.as_ref() }
+
+    pub fn talking(&self)->bool { self.conversation.is_some() }
+
+    pub fn next_sound(&mut self)->Option<Sounds> {
+	self.sounds.pop_back()
+    }
+
+    pub fn new(world:&World,name:&str)->Self {
+	let TileAddress { room_id:room,iy:hi,ix:hj } =
+	    world.start.expect("No start position!");
+	Hero{
+	    room,
+	    position:Position::Block(hi,hj,Facing::Right),
+	    inventory:Inventory::new(world.inventory_capacity),
+	    travel_request:None,
+	    name:name.to_string(),
+	    log:VecDeque::new(),
+	    fat:0,
+	    coins:0,
+	    foods:0,
+	    won:false,
+	    lost:false,
+	    sounds:VecDeque::new(),
+	    ticks:0,
+	    rainbow:false,
+	    entered:BTreeMap::new(),
+	    picked:Vec::new(),
+	    score:0,
+	    rng:MiniRNG::new(1),
+	    health:world.hazards.health,
+	    lives:world.hazards.lives,
+	    dead:false,
+	    respawn:None,
+	    pending_damage:0,
+	    pending_cause:None,
+	    last_room:None,
+	    events:VecDeque::new(),
+	    scripts_run:Vec::new(),
+	    conversation:None
+	}
+    }
+
+    /// Put the hero at a given tile, standing still
+    pub fn place(&mut self,ta:TileAddress) {
+	let f = self.position.facing();
+	self.room = ta.room_id;
+	self.position = Position::Block(ta.iy,ta.ix,f);
+	self.travel_request = None;
+    }
+
+    fn can_stand_in(&self,world:&World)->bool {
+	let (hi,hj) =
+	    match self.position {
+		Position::Block(hi,hj,_) => (hi,hj),
+		Position::Walking{ to, .. } => to
+	    };
+	match world.room(self.room) {
+	    None => false,
+	    Some(room_ptr) => {
+		let rm = room_ptr.yank();
+		hi < rm.rows && hj < rm.cols && Self::can_stand_on(world,rm.map[[hi,hj]])
+	    }
+	}
+    }
+
+    fn can_stand_on(world:&World,tile:Tile)->bool {
+	match tile {
+	    Tile::Empty | Tile::Plate | Tile::Door(_) | Tile::Vortex => true,
+	    Tile::Fire(_) | Tile::Water(_) => world.hazards.enabled,
+	    Tile::Custom { code,.. } => world.tile_kind(code).passable,
+	    Tile::Ice | Tile::Conveyor(_) | Tile::OneWay(_) | Tile::MetalRamp(_) => true,
+	    _ => false
+	}
+    }
+
+    /// Ticks taken by a step
+    fn step_ticks(&self)->usize {
+	if self.is_fat() {
+	    32
+	} else {
+	    8
+	}
+    }
+
+    /// Get ready for the harm or noise of stepping onto a tile
+    fn brace(&mut self,world:&World,tile:Tile) {
+	match tile {
+	    Tile::Fire(p) if world.hazards.enabled && world.hazards.fire_hurts(p.i) =>
+		self.pending_damage = world.hazards.fire_damage,
+	    Tile::Custom { code,.. } => {
+		let tt = world.tile_kind(code);
+		if world.hazards.enabled && tt.damage > 0 {
+		    self.pending_damage = tt.damage;
+		    self.pending_cause = Some(msg!("tile_hurts",tt.name.as_str()));
+		}
+		if let Some(sound) = tt.sound {
+		    self.sound(sound);
+		}
+	    },
+	    _ => ()
+	}
+    }
+
+    /// Make sure the hero is at a valid position in a (new version of
+    /// the) world, moving it to the start position if necessary.  On
+    /// error the hero is left untouched.
+    pub fn relocate(&mut self,world:&World)->Result<()> {
+	let start =
+	    if self.can_stand_in(world) {
+		None
+	    } else {
+		match world.start {
+		    None => bail!("No start position"),
+		    Some(ta) => Some(ta)
+		}
+	    };
+	let lost =
+	    if world.inventory_capacity != self.inventory.capacity() {
+		self.inventory.set_capacity(world.inventory_capacity)
+	    } else {
+		Vec::new()
+	    };
+	match start {
+	    Some(ta) => {
+		self.place(ta);
+		self.tell(msg!("world_changed"));
+	    },
+	    None =>
+		if let Position::Walking{ to:(hi,hj), .. } = self.position {
+		    self.place(TileAddress { room_id:self.room,iy:hi,ix:hj });
+		}
+	}
+	self.put_back(world,lost);
+	Ok(())
+    }
+
+    /// Leave objects that no longer fit in the inventory on the empty
+    /// tiles nearest to the hero
+    fn put_back(&self,world:&World,objs:Vec<Object>) {
+	if objs.is_empty() {
+	    return;
+	}
+	let at = self.standing();
+	let room_ptr =
+	    match world.room(self.room) {
+		None => return,
+		Some(room_ptr) => room_ptr
+	    };
+	let mut rm = room_ptr.yank_mut();
+	let mut free = Vec::new();
+	for iy in 0..rm.rows {
+	    for ix in 0..rm.cols {
+		if rm.map[[iy,ix]] == Tile::Empty && (iy,ix) != (at.iy,at.ix) {
+		    free.push((iy,ix));
+		}
+	    }
+	}
+	free.sort_by_key(|&(iy,ix)| iy.abs_diff(at.iy) + ix.abs_diff(at.ix));
+	for (obj,(iy,ix)) in objs.into_iter().zip(free) {
+	    rm.map[[iy,ix]] = Tile::Object(obj);
+	}
+    }
+
+    /// Move the hero to a given tile, if it can stand there
+    pub fn teleport(&mut self,world:&World,ta:TileAddress)->Result<()> {
+	let (room,position) = (self.room,self.position);
+	self.place(ta);
+	if self.can_stand_in(world) {
+	    Ok(())
+	} else {
+	    self.room = room;
+	    self.position = position;
+	    bail!("Cannot stand at ({},{}) in room {}",ta.iy,ta.ix,ta.room_id)
+	}
+    }
+
+    /// Show text given by the world as is
+    pub fn say(&mut self,text:&str) {
+	self.tell(Message::verbatim(text));
+    }
+
+    /// Show a message, keeping the previous ones in the log
+    pub fn tell(&mut self,msg:Message) {
+	if self.log.back() != Some(&msg) {
+	    if self.log.len() == Self::LOG_LENGTH {
+		self.log.pop_front();
+	    }
+	    self.log.push_back(msg);
+	}
+    }
+
+    fn talk(&mut self,dialogue:&Dialogue) {
+	if !dialogue.is_empty() {
+	    self.conversation = Some(Conversation::new(dialogue.clone()));
+	}
+    }
+
+    /// Select another answer of the dialogue
+    pub fn choose(&mut self,delta:isize) {
+	if let Some(c) = self.conversation.as_mut() {
+	    c.choose(delta);
+	}
+    }
+
+    /// Turn the page of the dialogue, closing it at the end
+    pub fn advance(&mut self) {
+	if let Some(c) = self.conversation.as_mut() {
+	    if !c.advance() {
+		self.conversation = None;
+	    }
+	}
+    }
+
+    /// Select the next (delta = 1) or previous (delta = -1) carried
+    /// object
+    pub fn cycle(&mut self,world:&World,delta:isize) {
+	self.inventory.cycle(delta);
+	self.say_selected(world);
+    }
+
+    /// Select the object in slot `i`
+    pub fn select(&mut self,world:&World,i:usize) {
+	self.inventory.select(i);
+	self.say_selected(world);
+    }
+
+    fn say_selected(&mut self,world:&World) {
+	match self.inventory.selected() {
+	    None => self.tell(msg!("carry_nothing")),
+	    Some(obj) => self.tell(msg!("hold",&world.object_kind(obj)))
+	}
+    }
+
+    pub fn carry(&mut self,world:&World,obj:Object) {
+	let ot = world.object_kind(obj);
+	if ot.is_carried() && !self.inventory.add(obj) {
+	    return;
+	}
+	self.events.push_back(Event::PickUp(obj));
+	match self.picked.iter_mut().find(|(o,_)| *o == obj) {
+	    Some((_,n)) => *n += 1,
+	    None => self.picked.push((obj,1))
+	}
+	self.sound(ot.sound);
+	self.score += ot.score;
+	if ot.food {
+	    if ot.fattening {
+		self.fat = FAT_PENALTY;
+		self.tell(msg!("eat_fat",&ot));
+	    } else {
+		self.tell(msg!("eat",&ot));
+	    }
+	    self.foods += 1;
+	} else if ot.coin {
+	    self.coins += 1;
+	} else {
+	    self.tell(msg!("take",&ot));
+	}
+    }
+
+    pub fn drop(&mut self,world:&mut World) {
+	match self.position {
+	    Position::Block(hi,hj,f) =>
+		match self.inventory.selected() {
+		    None => self.tell(msg!("carry_nothing")),
+		    Some(obj) => {
+			let room_ptr =
+			    match world.room(self.room) {
+				None => return,
+				Some(room_ptr) => room_ptr
+			    };
+			let mut rm = room_ptr.yank_mut();
+			if world.hazards.enabled && world.hazards.boat == Some(obj) &&
+			    matches!(rm.map[[hi,hj]],Tile::Water(_)) {
+			    self.tell(msg!("cannot_drop_boat",&world.object_kind(obj)));
+			    return;
+			}
+			let (di,dj) = f.to_deltas();
+			let (hi,hj) = (hi as isize + di,hj as isize + dj);
+			if 0 <= hi && hi < rm.rows as isize && 0 <= hj && hj < rm.cols as isize {
+			    if rm.map[[hi as usize,hj as usize]] == Tile::Empty {
+				rm.map[[hi as usize,hj as usize]] = Tile::Object(obj);
+				let _ = self.inventory.take_selected();
+				self.tell(msg!("drop",&world.object_kind(obj)));
+			    } else {
+				self.tell(msg!("cannot_drop_over",&world.object_kind(obj)));
+			    }
+			} else {
+			    self.tell(msg!("cannot_drop_edge"));
+			}
+		    }
+		},
+	    Position::Walking{ .. } => self.tell(msg!("cannot_drop_walking"))
+	}
+    }
+
+    pub fn pending(&mut self,world:&mut World) {
+	match self.travel_request {
+	    None => (),
+	    Some((di,dj)) => self.travel(world,di,dj)
+	}
+    }
+
+    pub fn tick(&mut self,world:&mut World) {
+	//println!("HERO: {:?}",self);
+	self.ticks += 1;
+	self.entered.entry(self.room).or_insert(self.ticks);
+	if self.last_room != Some(self.room) {
+	    self.last_room = Some(self.room);
+	    self.events.push_back(Event::EnterRoom(self.room));
+	}
+	self.pending(world);
+	match &mut self.position {
+	    Position::Block(_,_,_) => {
+		self.pending(world);
+		self.slide(world,false);
+	    },
+	    Position::Walking{ from:_,to,ref mut step,total } => {
+		if self.fat > 0 {
+		    self.fat -= 1;
+		}
+		*step += 1;
+		if *step == *total {
+		    self.position = Position::Block(to.0,to.1,self.position.facing());
+		    self.arrive(world);
+		    if !self.slide(world,true) {
+			self.pending(world);
+		    }
+		    self.sound(Sounds::Walk);
+		}
+	    }
+	}
+	if !self.dead && !self.talking() {
+	    self.move_creatures(world);
+	}
+	if world.update_plates(Some(self.standing())) {
+	    self.sound(Sounds::Switch);
+	}
+	self.run_scripts(world);
+	self.check_goals(world);
+    }
+
+    /// Let the floor move a hero standing still: conveyors always, ice
+    /// and ramps when `arriving` on them.  Returns whether the hero
+    /// moves.
+    fn slide(&mut self,world:&mut World,arriving:bool)->bool {
+	if self.dead || self.talking() {
+	    return false;
+	}
+	let f =
+	    match self.position {
+		Position::Block(_,_,f) => f,
+		Position::Walking{ .. } => return false
+	    };
+	let drift =
+	    match world.get_tile(&self.standing()) {
+		Some(t @ Tile::Conveyor(_)) => t.drift(f),
+		Some(t) if arriving => t.drift(f),
+		_ => None
+	    };
+	match drift {
+	    None => false,
+	    Some(g) => {
+		self.drift(world,g)
+	    }
+	}
+    }
+
+    /// Let the floor carry the hero one tile along `g`, onto a tile it
+    /// can stand on, without using anything there.  Returns whether the
+    /// hero moves.
+    fn drift(&mut self,world:&World,g:Facing)->bool {
+	let (hi0,hj0) =
+	    match self.position {
+		Position::Block(hi,hj,_) => (hi,hj),
+		Position::Walking{ .. } => return false
+	    };
+	let (di,dj) = g.to_deltas();
+	let (hi,hj) = (hi0 as isize + di,hj0 as isize + dj);
+	if hi < 0 || hj < 0 {
+	    return false;
+	}
+	let to = TileAddress { room_id:self.room,iy:hi as usize,ix:hj as usize };
+	if world.creature_at(&to).is_some() {
+	    return false;
+	}
+	let tile =
+	    match world.get_tile(&to) {
+		None => return false,
+		Some(tile) => tile
+	    };
+	let ok =
+	    match tile {
+		Tile::Door(_) | Tile::Vortex => false,
+		Tile::OneWay(_) | Tile::MetalRamp(_) => tile.admits(g),
+		Tile::Water(_) => world.hazards.enabled &&
+		    world.hazards.boat.map(|boat| self.inventory.contains(boat)).unwrap_or(false),
+		_ => Self::can_stand_on(world,tile)
+	    };
+	if !ok {
+	    return false;
+	}
+	self.brace(world,tile);
+	self.position = Position::Walking{
+	    from:(hi0,hj0),
+	    to:(to.iy,to.ix),
+	    step:0,
+	    total:self.step_ticks()
+	};
+	true
+    }
+
+    /// Run the scripts of the world for the events that happened
+    fn run_scripts(&mut self,world:&World) {
+	while let Some(event) = self.events.pop_front() {
+	    for script in world.scripts.iter() {
+		let run = self.scripts_run.contains(script);
+		if script.on != event || (script.once && run) {
+		    continue;
+		}
+		if !run {
+		    self.scripts_run.push(script.clone());
+		}
+		for action in script.actions.iter() {
+		    self.act(world,action);
+		}
+	    }
+	}
+    }
+
+    fn act(&mut self,world:&World,action:&Action) {
+	match action {
+	    Action::Say(msg) => self.say(msg),
+	    &Action::SetTile(ta,tile) => world.set_tile(&ta,tile),
+	    Action::ClearTile(ta) => world.set_tile(ta,Tile::Empty),
+	    &Action::Give(obj) if world.object_kind(obj).coin => self.coins += 1,
+	    &Action::Give(obj) => {
+		let _ = self.inventory.add(obj);
+	    },
+	    &Action::Take(obj) if world.object_kind(obj).coin => self.coins = self.coins.saturating_sub(1),
+	    &Action::Take(obj) => {
+		let _ = self.inventory.remove(obj);
+	    },
+	    &Action::Sound(snd) => self.sound(snd),
+	    &Action::Teleport(ta) => {
+		if self.teleport(world,ta).is_ok() {
+		    self.sound(Sounds::Teleport);
+		}
+	    }
+	}
+    }
+
+    /// Move the creatures of the hero's room, which may run into the
+    /// hero
+    fn move_creatures(&mut self,world:&mut World) {
+	let room_ptr =
+	    match world.room(self.room) {
+		None => return,
+		Some(room_ptr) => room_ptr
+	    };
+	let hero = self.standing();
+	let hero = (hero.iy,hero.ix);
+	let mut met = Vec::new();
+	{
+	    let rm = room_ptr.yank();
+	    for i in 0..world.creatures.len() {
+		if world.creatures[i].room != self.room {
+		    continue;
+		}
+		let blocked : Vec<(usize,usize)> = world.creatures.iter().enumerate()
+		    .filter(|&(k,c)| k != i && c.room == self.room)
+		    .map(|(_,c)| c.tile())
+		    .collect();
+		let creature = &mut world.creatures[i];
+		creature.tick(&rm,hero,&blocked,&mut self.rng);
+		if creature.meets(hero) {
+		    met.push(i);
+		}
+	    }
+	}
+	for i in met {
+	    self.meet(world,i);
+	}
+    }
+
+    /// Contact with a creature
+    fn meet(&mut self,world:&mut World,i:usize) {
+	let creature = &mut world.creatures[i];
+	creature.calm();
+	let name = creature.name.clone();
+	match creature.contact.clone() {
+	    Contact::Hurt(damage) if world.hazards.enabled =>
+		self.hurt(world,damage,msg!("creature_hurts",name.as_str())),
+	    Contact::Hurt(_) => self.tell(msg!("creature_bumps",name.as_str())),
+	    Contact::Say(text) => self.tell(msg!("creature_says",name.as_str(),text.as_str())),
+	    Contact::Talk(dialogue) => {
+		self.say(&name);
+		self.talk(&dialogue);
+	    }
+	}
+    }
+
+    /// The tile the hero stands on, or is stepping onto
+    fn standing(&self)->TileAddress {
+	let (iy,ix) =
+	    match self.position {
+		Position::Block(iy,ix,_) => (iy,ix),
+		Position::Walking{ to, .. } => to
+	    };
+	TileAddress { room_id:self.room,iy,ix }
+    }
+
+    /// End of a step: apply damage and record checkpoints
+    fn arrive(&mut self,world:&World) {
+	self.events.push_back(Event::StepOn(self.standing()));
+	if self.pending_damage > 0 {
+	    let damage = self.pending_damage;
+	    self.pending_damage = 0;
+	    let how = self.pending_cause.take().unwrap_or_else(|| msg!("burnt"));
+	    self.hurt(world,damage,how);
+	    return;
+	}
+	if let Position::Block(iy,ix,_) = self.position {
+	    let ta = TileAddress { room_id:self.room,iy,ix };
+	    if world.hazards.is_checkpoint(&ta) && self.respawn != Some(ta) {
+		self.respawn = Some(ta);
+		self.tell(msg!("checkpoint"));
+	    }
+	}
+    }
+
+    fn hurt(&mut self,world:&World,damage:usize,how:Message) {
+	self.health = self.health.saturating_sub(damage);
+	if self.health == 0 {
+	    self.die(world);
+	} else {
+	    self.sound(Sounds::Hurt);
+	    self.tell(msg!("hurt",how,self.health));
+	}
+    }
+
+    fn die(&mut self,world:&World) {
+	self.sound(Sounds::Die);
+	if let Some(n) = self.lives {
+	    if n <= 1 {
+		self.lives = Some(0);
+		self.dead = true;
+		self.lost = true;
+		self.tell(msg!("die"));
+		return;
+	    }
+	    self.lives = Some(n - 1);
+	}
+	self.health = world.hazards.health;
+	let back = self.respawn.into_iter().chain(world.start)
+	    .any(|ta| self.teleport(world,ta).is_ok());
+	if back {
+	    self.tell(msg!("respawn"));
+	} else {
+	    self.tell(msg!("no_respawn"));
+	}
+    }
+
+    /// Number of an object collected so far: those picked up for
+    /// consumables, those carried for the others
+    pub fn collected(&self,world:&World,obj:Object)->usize {
+	if !world.object_kind(obj).is_carried() {
+	    self.picked.iter()
+		.find(|(o,_)| *o == obj)
+		.map(|&(_,n)| n)
+		.unwrap_or(0)
+	} else {
+	    self.inventory.items().iter().filter(|&&o| o == obj).count()
+	}
+    }
+
+    pub fn goal_status(&self,world:&World,goal:&Goal)->Status {
+	let done = |x:bool| if x { Status::Done } else { Status::Pending };
+	match goal {
+	    Goal::ReachRainbow => done(self.rainbow),
+	    Goal::CollectAllCoins => done(world.count_coins() == 0),
+	    Goal::CoverAllPlates => done(world.count_tiles(|t| t == Tile::Plate) == 0),
+	    &Goal::Collect { object,count } => done(self.collected(world,object) >= count),
+	    &Goal::Deliver { object,to } => done(world.get_tile(&to) == Some(Tile::Object(object))),
+	    &Goal::ReachRoom { room,within } => {
+		let limit = within.map(|s| s*TICKS_PER_SECOND);
+		match (self.entered.get(&room),limit) {
+		    (Some(_),None) => Status::Done,
+		    (Some(&t),Some(l)) => if t <= l { Status::Done } else { Status::Failed },
+		    (None,Some(l)) if self.ticks > l => Status::Failed,
+		    (None,_) => Status::Pending
+		}
+	    }
+	}
+    }
+
+    /// Seconds left to achieve a timed goal
+    pub fn time_left(&self,goal:&Goal)->Option<usize> {
+	match goal {
+	    &Goal::ReachRoom { room,within:Some(s) } if !self.entered.contains_key(&room) =>
+		Some((s*TICKS_PER_SECOND).saturating_sub(self.ticks) / TICKS_PER_SECOND),
+	    _ => None
+	}
+    }
+
+    /// Description of a goal, with the time left if it is timed
+    pub fn objective(&self,world:&World,goal:&Goal)->Message {
+	match self.time_left(goal) {
+	    None => goal.message(world),
+	    Some(s) => msg!("goal.time_left",goal.message(world),s)
+	}
+    }
+
+    fn check_goals(&mut self,world:&World) {
+	if self.won || self.lost {
+	    return;
+	}
+	let statuses : Vec<Status> = world.active_goals().iter()
+	    .map(|goal| self.goal_status(world,goal))
+	    .collect();
+	if statuses.contains(&Status::Failed) {
+	    self.lost = true;
+	    self.tell(msg!("time_up"));
+	} else if statuses.iter().all(|&s| s == Status::Done) {
+	    self.won = true;
+	    self.tell(msg!("won",self.name.as_str()));
+	}
+    }
+
+    pub fn sound(&mut self,snd:Sounds) {
+	self.sounds.push_back(snd);
+    }
+
+    pub fn start(&mut self,di:isize,dj:isize) {
+	let f2 = Facing::from_deltas(di,dj);
+	match &mut self.position {
+	    Position::Block(_hi,_hj,f1) => *f1 = f2,
+	    Position::Walking{ .. } => ()
+	}
+	self.travel_request = Some((di,dj))
+    }
+
+    pub fn stop(&mut self,di:isize,dj:isize) {
+	if self.travel_request == Some((di,dj)) {
+	    self.travel_request = None;
+	}
+    }
+
+    pub fn is_fat(&self)->bool {
+	self.fat > 0
+    }
+
+    pub fn travel(&mut self,world:&mut World,di:isize,dj:isize) {
+	if self.dead || self.talking() {
+	    return;
+	}
+	let total = self.step_ticks();
+	// let hr = self.room;
+	let mut vortex = None;
+	let mut fired = None;
+	let mut lever = None;
+	let mut slot = None;
+	let mut met = None;
+	match self.position {
+	    Position::Walking{ .. } => (),
+	    Position::Block(hi0,hj0,f) => {
+		let (hi,hj) = (hi0 as isize + di,hj0 as isize + dj);
+		let room_ptr =
+		    match world.room(self.room) {
+			None => return,
+			Some(room_ptr) => room_ptr
+		    };
+		let mut rm = room_ptr.yank_mut();
+		if (0 <= hi) & (0 <= hj) {
+		    let (hi,hj) = (hi as usize,hj as usize);
+		    if (hi < rm.rows) & (hj < rm.cols) {
+			let creature = world.creature_at(&TileAddress { room_id:self.room,iy:hi,ix:hj });
+			match &mut rm.map[[hi,hj]] {
+			    _ if creature.is_some() => met = creature,
+			    Tile::Empty | Tile::Plate => {
+				self.position = Position::Walking{
+				    from:(hi0,hj0),
+				    to:(hi,hj),
+				    step:0,
+				    total
+				}
+			    },
+			    Tile::Rainbow => {
+				self.rainbow = true;
+				self.tell(msg!("rainbow"));
+			    },
+			    &mut Tile::Object(o) => {
+				let ok =
+				    if !world.object_kind(o).is_carried() || !self.inventory.is_full() {
+					true
+				    } else {
+					self.tell(msg!("hands_full",&world.object_kind(o)));
+					false
+				    };
+				if ok {
+				    self.carry(world,o);
+				    rm.map[[hi,hj]] = Tile::Empty;
+				    self.position = Position::Walking{
+					from:(hi0,hj0),
+					to:(hi,hj),
+					step:0,
+					total
+				    }
+				}
+			    },
+			    &mut Tile::Door(Door{ target, key, ref mut locked, requires, .. }) => {
+				let ok =
+				    if !self.meets(requires) {
+					false
+				    } else if *locked && world.is_switched(&TileAddress { room_id:self.room,iy:hi,ix:hj }) {
+					self.tell(msg!("door_remote"));
+					false
+				    } else if *locked {
+					let has_key =
+					    match key {
+						None => self.inventory.is_empty(),
+						Some(k) => self.inventory.remove(k)
+					    };
+					if has_key {
+					    *locked = false;
+					    self.events.push_back(Event::Unlock(TileAddress { room_id:self.room,iy:hi,ix:hj }));
+					    match &key {
+						None => self.tell(msg!("door_open")),
+						Some(o) => self.tell(msg!("door_unlock",&world.object_kind(*o)))
+					    };
+					    true
+					} else {
+					    match &key {
+						None => self.tell(msg!("door_forever")),
+						Some(o) => self.tell(msg!("need",&world.object_kind(*o)))
+					    };
+					    false
+					}
+				    } else {
+					true
+				    };
+				if ok {
+				    let through =
+					match &target {
+					    None => {
+						self.tell(msg!("door_broken"));
+						false
+					    },
+					    &Some(Target{ room, door }) => {
+						if room == rm.id {
+						    self.traverse(&rm,door,f)
+						} else {
+						    match world.room(room) {
+							None => {
+							    self.tell(msg!("door_nowhere"));
+							    false
+							},
+							Some(target_room_ptr) => {
+							    let target_room = target_room_ptr.yank();
+							    self.traverse(&target_room,door,f)
+							}
+						    }
+						}
+					    }
+					};
+				    if through {
+					self.pay(requires);
+				    }
+				}
+			    },
+			    Tile::Vortex => vortex = Some(TileAddress { room_id:self.room,iy:hi,ix:hj }),
+			    Tile::Lever { .. } => lever = Some(TileAddress { room_id:self.room,iy:hi,ix:hj }),
+			    Tile::CoinSlot { paid:true } => self.tell(msg!("slot_paid")),
+			    Tile::Sign =>
+				match world.sign_at(&TileAddress { room_id:self.room,iy:hi,ix:hj }) {
+				    None => self.tell(msg!("sign_blank")),
+				    Some(dialogue) => self.talk(dialogue)
+				},
+			    Tile::CoinSlot { .. } => {
+				if self.coins > 0 {
+				    slot = Some(TileAddress { room_id:self.room,iy:hi,ix:hj });
+				} else {
+				    self.tell(msg!("insert_coin"));
+				}
+			    },
+			    Tile::Crate { .. } => {
+				if Self::push(&mut rm,(hi,hj),(di,dj)) {
+				    self.sound(Sounds::Push);
+				    self.position = Position::Walking{
+					from:(hi0,hj0),
+					to:(hi,hj),
+					step:0,
+					total
+				    }
+				} else {
+				    self.tell(msg!("wont_budge"));
+				}
+			    },
+			    &mut Tile::Fire(p) if world.hazards.enabled => {
+				self.brace(world,Tile::Fire(p));
+				self.position = Position::Walking{
+				    from:(hi0,hj0),
+				    to:(hi,hj),
+				    step:0,
+				    total
+				}
+			    },
+			    &mut Tile::Custom { code,phase } if world.tile_kind(code).passable => {
+				self.brace(world,Tile::Custom { code,phase });
+				self.position = Position::Walking{
+				    from:(hi0,hj0),
+				    to:(hi,hj),
+				    step:0,
+				    total
+				}
+			    },
+			    t if t.admits(Facing::from_deltas(di,dj)) => {
+				self.position = Position::Walking{
+				    from:(hi0,hj0),
+				    to:(hi,hj),
+				    step:0,
+				    total
+				}
+			    },
+			    Tile::OneWay(_) => self.tell(msg!("one_way")),
+			    Tile::Water(_) if world.hazards.enabled => {
+				match world.hazards.boat {
+				    Some(boat) if self.inventory.contains(boat) => {
+					self.sound(Sounds::Splash);
+					self.position = Position::Walking{
+					    from:(hi0,hj0),
+					    to:(hi,hj),
+					    step:0,
+					    total
+					}
+				    },
+				    Some(boat) => self.tell(msg!("need_boat",&world.object_kind(boat))),
+				    None => self.tell(msg!("cannot_swim"))
+				}
+			    },
+			    _ => () // self.sound(Sounds::BlockedAgainstWall)
+			}
+		    }
+		}
+	    }
+	}
+	if let Some(at) = vortex {
+	    self.enter_vortex(world,at);
+	}
+	if let Some(i) = met {
+	    self.meet(world,i);
+	}
+	if let Some(at) = lever {
+	    // A lever cannot close a wall on the hero
+	    if world.can_fire(&at,Some(self.standing())) {
+		if let Some(Tile::Lever { on }) = world.get_tile(&at) {
+		    world.set_tile(&at,Tile::Lever { on:!on });
+		}
+		fired = Some(at);
+	    } else {
+		self.tell(msg!("wont_budge"));
+	    }
+	}
+	if let Some(at) = slot {
+	    if world.can_fire(&at,Some(self.standing())) {
+		self.coins -= 1;
+		world.set_tile(&at,Tile::CoinSlot { paid:true });
+		fired = Some(at);
+	    } else {
+		self.tell(msg!("wont_budge"));
+	    }
+	}
+	if let Some(at) = fired {
+	    world.fire_trigger(&at,Some(self.standing()));
+	    self.sound(Sounds::Switch);
+	    self.tell(msg!("click"));
+	}
+	self.run_scripts(world);
+    }
+
+    /// Push the line of crates starting at `(iy,ix)` one tile along
+    /// `(di,dj)`, if there is room for the last one
+    fn push(rm:&mut Room,(iy,ix):(usize,usize),(di,dj):(isize,isize))->bool {
+	let inside = |i:isize,j:isize| 0 <= i && i < rm.rows as isize && 0 <= j && j < rm.cols as isize;
+	let (mut i,mut j) = (iy as isize,ix as isize);
+	let mut n = 0;
+	loop {
+	    if !inside(i,j) {
+		return false;
+	    }
+	    match rm.map[[i as usize,j as usize]] {
+		Tile::Crate { .. } => n += 1,
+		Tile::Empty | Tile::Plate => break,
+		_ => return false
+	    }
+	    i += di;
+	    j += dj;
+	}
+	for k in (0..n).rev() {
+	    let (si,sj) = ((iy as isize + k*di) as usize,(ix as isize + k*dj) as usize);
+	    let (ti,tj) = ((si as isize + di) as usize,(sj as isize + dj) as usize);
+	    if let Some(((left,_),(_,pushed))) = rm.map[[si,sj]].floor().zip(rm.map[[ti,tj]].floor()) {
+		rm.map[[ti,tj]] = pushed;
+		rm.map[[si,sj]] = left;
+	    }
+	}
+	true
+    }
+
+    fn enter_vortex(&mut self,world:&World,at:TileAddress) {
+	let to =
+	    match world.vortex_destination(&at) {
+		None => None,
+		Some(Destination::Tile(ta)) => Some(ta),
+		Some(Destination::Random) => {
+		    let others : Vec<TileAddress> = world.vortex_list().into_iter()
+			.filter(|&ta| ta != at)
+			.collect();
+		    if others.is_empty() {
+			None
+		    } else {
+			Some(others[self.rng.sample_u32(others.len() as u32) as usize])
+		    }
+		}
+	    };
+	match to {
+	    None => self.tell(msg!("vortex_nowhere")),
+	    Some(ta) =>
+		if self.teleport(world,ta).is_ok() {
+		    self.sound(Sounds::Teleport);
+		    self.tell(msg!("vortex_swallows"));
+		} else {
+		    self.tell(msg!("vortex_spits"));
+		}
+	}
+    }
+
+    /// Does the hero meet the requirement of a door?  Says why not.
+    fn meets(&mut self,requires:Option<Requirement>)->bool {
+	match requires {
+	    None => true,
+	    Some(Requirement::Coins { count,consume }) => {
+		if self.coins >= count {
+		    true
+		} else if consume {
+		    self.tell(msg!("toll_short",count,self.coins));
+		    false
+		} else {
+		    self.tell(msg!("coins_short",count,self.coins));
+		    false
+		}
+	    },
+	    Some(Requirement::Foods { count }) => {
+		if self.foods >= count {
+		    true
+		} else {
+		    self.tell(msg!("foods_short",count - self.foods));
+		    false
+		}
+	    }
+	}
+    }
+
+    fn pay(&mut self,requires:Option<Requirement>) {
+	if let Some(Requirement::Coins { count,consume:true }) = requires {
+	    if count > 0 {
+		self.coins -= count;
+		self.tell(msg!("pay",count));
+	    }
+	}
+    }
+
+    /// Come out of a door of a room, returning whether it exists
+    fn traverse(&mut self,room:&Room,door:usize,f:Facing)->bool {
+	match room.locate_door(door) {
+	    None => {
+		self.tell(msg!("door_nowhere"));
+		false
+	    },
+	    Some((hi,hj)) => {
+		self.room = room.id;
+		self.travel_request = None;
+		self.position = Position::Block(hi,hj,f);
+		self.respawn = Some(TileAddress { room_id:room.id,iy:hi,ix:hj });
+		self.sound(Sounds::GoThroughDoor);
+		true
+	    }
+	}
+    }
+}
+
+#[test]
+fn test_relocate() {
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["#H  #"]).unwrap();
+    let mut hero = Hero::new(&world,"TEST");
+    hero.place(TileAddress { room_id:1,iy:0,ix:4 });
+    assert!(hero.relocate(&world).is_ok());
+    assert_eq!(hero.room(),1);
+    assert!(matches!(hero.position(),Position::Block(0,1,_)));
+
+    hero.place(TileAddress { room_id:1,iy:0,ix:2 });
+    assert!(hero.relocate(&world).is_ok());
+    assert!(matches!(hero.position(),Position::Block(0,2,_)));
+
+    hero.inventory.add(Object::KEY);
+    hero.inventory.add(Object::TOY_CAR);
+    let capacity = hero.inventory().capacity();
+    let mut other = World::new();
+    other.add_room(2,"TWO",&["  "]).unwrap();
+    other.inventory_capacity = 1;
+    assert!(hero.relocate(&other).is_err());
+    assert_eq!(hero.inventory().items(),[Object::KEY,Object::TOY_CAR]);
+    assert_eq!(hero.inventory().capacity(),capacity);
+
+    world.inventory_capacity = 1;
+    assert!(hero.relocate(&world).is_ok());
+    assert_eq!(hero.inventory().items(),[Object::KEY]);
+    assert_eq!(world.get_tile(&TileAddress { room_id:1,iy:0,ix:1 }),Some(Tile::Object(Object::TOY_CAR)));
+
+    world.start = None;
+    hero.place(TileAddress { room_id:2,iy:0,ix:2 });
+    assert!(hero.relocate(&world).is_err());
+}
+
+#[test]
+fn test_unlock_from_inventory() {
+    let mut world = World::new();
+    world.inventory_capacity = 2;
+    world.start = world.add_room(1,"ONE",&["#HKTS0#"]).unwrap();
+    world.lock_door_with(1,0,Object::KEY);
+    let mut hero = Hero::new(&world,"TEST");
+    for _ in 0..4 {
+	hero.travel(&mut world,0,1);
+	for _ in 0..32 {
+	    hero.tick(&mut world);
+	}
+    }
+    assert_eq!(hero.inventory().items(),&[Object::KEY,Object::TOY_CAR]);
+    assert!(matches!(hero.position(),Position::Block(0,3,_)));
+    assert_eq!(hero.message(),"YOU CANNOT CARRY SOME SQUARES AND TRIANGLES, YOUR HANDS ARE FULL");
+
+    world.set_tile(&TileAddress { room_id:1,iy:0,ix:4 },Tile::Empty);
+    hero.travel(&mut world,0,1);
+    for _ in 0..32 {
+	hero.tick(&mut world);
+    }
+    hero.travel(&mut world,0,1);
+    assert!(!world.get_door(1,0).unwrap().locked);
+    assert_eq!(hero.inventory().items(),&[Object::TOY_CAR]);
+}
+
+#[test]
+fn test_toll() {
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["0HCC1"]).unwrap();
+    world.add_room(2,"TWO",&["0"]).unwrap();
+    world.reconnect(1,0,2,0).unwrap();
+    world.require(1,0,Requirement::Coins { count:2,consume:true });
+    world.require(1,1,Requirement::Foods { count:1 });
+    let mut hero = Hero::new(&world,"TEST");
+    hero.travel(&mut world,0,-1);
+    assert_eq!(hero.room(),1);
+    assert_eq!(hero.message(),"THE TOLL IS 2 COINS, YOU HAVE 0");
+    for _ in 0..2 {
+	hero.travel(&mut world,0,1);
+	for _ in 0..32 {
+	    hero.tick(&mut world);
+	}
+    }
+    hero.travel(&mut world,0,1);
+    assert_eq!(hero.message(),"YOU MUST EAT 1 MORE FOODS TO PASS");
+    hero.place(TileAddress { room_id:1,iy:0,ix:1 });
+    let two = world.rooms.remove(&2).unwrap();
+    hero.travel(&mut world,0,-1);
+    assert_eq!(hero.message(),"THIS DOOR LEADS NOWHERE");
+    assert_eq!(hero.coins(),2);
+    world.rooms.insert(2,two);
+    hero.travel(&mut world,0,-1);
+    assert_eq!(hero.room(),2);
+    assert_eq!(hero.coins(),0);
+}
+
+#[test]
+fn test_goals() {
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["HCK0"]).unwrap();
+    world.add_room(2,"TWO",&["0"]).unwrap();
+    world.reconnect(1,0,2,0).unwrap();
+    world.goals = vec![Goal::CollectAllCoins,
+		       Goal::Collect { object:Object::KEY,count:1 },
+		       Goal::ReachRoom { room:2,within:Some(1) }];
+    let mut hero = Hero::new(&world,"TEST");
+    for _ in 0..2 {
+	hero.travel(&mut world,0,1);
+	for _ in 0..8 {
+	    hero.tick(&mut world);
+	}
+	assert!(!hero.won());
+    }
+    hero.travel(&mut world,0,1);
+    hero.tick(&mut world);
+    assert!(hero.won());
+
+    world.goals = vec![Goal::ReachRoom { room:3,within:Some(1) }];
+    let mut hero = Hero::new(&world,"TEST");
+    for _ in 0..TICKS_PER_SECOND {
+	hero.tick(&mut world);
+    }
+    assert_eq!(hero.time_left(&world.goals[0]),Some(0));
+    assert!(!hero.lost());
+    hero.tick(&mut world);
+    assert!(hero.lost());
+}
+
+#[test]
+fn test_object_types() {
+    use crate::object::ObjectType;
+
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["HgdC"]).unwrap();
+    world.declare(ObjectType { consumable:true,score:10,..ObjectType::new('g',"A GEM",(2,0)) });
+    world.declare(ObjectType { coin:true,score:5,..ObjectType::new('d',"A DOUBLOON",(0,1)) });
+    world.goals = vec![Goal::CollectAllCoins,Goal::Collect { object:Object('g'),count:1 }];
+    assert_eq!(world.count_coins(),2);
+    let mut hero = Hero::new(&world,"TEST");
+    hero.travel(&mut world,0,1);
+    assert_eq!(hero.message(),"YOU TAKE A GEM");
+    assert!(hero.inventory().is_empty());
+    for _ in 0..3 {
+	for _ in 0..8 {
+	    hero.tick(&mut world);
+	}
+	hero.travel(&mut world,0,1);
+    }
+    assert_eq!((hero.coins(),hero.score()),(2,15));
+    hero.tick(&mut world);
+    assert!(hero.won());
+}
+
+#[test]
+fn test_vortex() {
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["H@ @"]).unwrap();
+    world.add_room(2,"TWO",&["#@ "]).unwrap();
+    let a = TileAddress { room_id:1,iy:0,ix:1 };
+    let b = TileAddress { room_id:2,iy:0,ix:1 };
+    let mut hero = Hero::new(&world,"TEST");
+    hero.travel(&mut world,0,1);
+    assert_eq!(hero.message(),"THIS VORTEX LEADS NOWHERE");
+    world.link_vortices(a,b).unwrap();
+    hero.travel(&mut world,0,1);
+    assert_eq!(hero.room(),2);
+    assert!(matches!(hero.position(),Position::Block(0,1,_)));
+    assert_eq!(hero.next_sound(),Some(Sounds::Teleport));
+
+    world.set_vortex(b,Some(Destination::Random)).unwrap();
+    hero.travel(&mut world,0,1);
+    for _ in 0..8 {
+	hero.tick(&mut world);
+    }
+    hero.travel(&mut world,0,-1);
+    assert_eq!(hero.room(),1);
+
+    world.crop_room(1,0,1,1,3).unwrap();
+    assert_eq!(world.start,None);
+    assert_eq!(world.vortex_destination(&TileAddress { room_id:1,iy:0,ix:0 }),
+	       Some(Destination::Tile(b)));
+    assert!(world.dangling_vortices().is_empty());
+    assert_eq!(world.incoming_vortices(2),[TileAddress { room_id:1,iy:0,ix:0 }]);
+    world.delete_room(2,true);
+    assert!(world.vortices.is_empty());
+}
+
+#[test]
+fn test_hazards() {
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["H~F"]).unwrap();
+    world.hazards.enabled = true;
+    world.hazards.health = 1;
+    world.hazards.lives = Some(2);
+    world.hazards.boat = Some(Object::TOY_CAR);
+    let mut hero = Hero::new(&world,"TEST");
+    hero.travel(&mut world,0,1);
+    assert_eq!(hero.message(),"YOU NEED A TOY CAR TO CROSS THE WATER");
+    hero.inventory.add(Object::TOY_CAR);
+    world.hazards.checkpoints.push(TileAddress { room_id:1,iy:0,ix:1 });
+    for _ in 0..2 {
+	hero.travel(&mut world,0,1);
+	for _ in 0..8 {
+	    hero.tick(&mut world);
+	}
+    }
+    assert_eq!(hero.lives(),Some(1));
+    assert_eq!(hero.health(),1);
+    assert!(matches!(hero.position(),Position::Block(0,1,_)));
+    hero.drop(&mut world);
+    assert_eq!(hero.message(),"YOU CANNOT DROP A TOY CAR ON THE WATER");
+    assert!(hero.inventory.contains(Object::TOY_CAR));
+    hero.travel(&mut world,0,1);
+    for _ in 0..8 {
+	hero.tick(&mut world);
+    }
+    assert!(hero.dead());
+}
+
+#[test]
+fn test_tile_types() {
+    use crate::tiles::TileType;
+
+    let mut world = World::new();
+    world.declare_tile(TileType { passable:true,damage:1,..TileType::new(':',"LAVA",(4,4)) });
+    world.declare_tile(TileType::new('=',"GLASS",(0,0)));
+    world.start = world.add_room(1,"ONE",&["=H:"]).unwrap();
+    let mut hero = Hero::new(&world,"TEST");
+    hero.travel(&mut world,0,-1);
+    assert!(matches!(hero.position(),Position::Block(0,1,_)));
+    hero.travel(&mut world,0,1);
+    for _ in 0..8 {
+	hero.tick(&mut world);
+    }
+    assert!(matches!(hero.position(),Position::Block(0,2,_)));
+    assert_eq!(hero.health(),world.hazards.health);
+    world.hazards.enabled = true;
+    hero.place(TileAddress { room_id:1,iy:0,ix:1 });
+    hero.travel(&mut world,0,1);
+    for _ in 0..8 {
+	hero.tick(&mut world);
+    }
+    assert_eq!(hero.message(),format!("YOU STEP ON LAVA, HEALTH {}",world.hazards.health - 1));
+}
+
+#[test]
+fn test_sliding() {
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&[
+	"H▒▒#",
+	"⇓##q",
+	" → s",
+    ]).unwrap();
+    let mut hero = Hero::new(&world,"TEST");
+    let run = |hero:&mut Hero,world:&mut World,n:usize| {
+	for _ in 0..n {
+	    hero.tick(world);
+	}
+    };
+    hero.travel(&mut world,0,1);
+    run(&mut hero,&mut world,40);
+    assert!(matches!(hero.position(),Position::Block(0,2,Facing::Right)));
+    hero.place(TileAddress { room_id:1,iy:1,ix:0 });
+    run(&mut hero,&mut world,1);
+    assert!(matches!(hero.position(),Position::Walking{ to:(2,0),.. }));
+    run(&mut hero,&mut world,8);
+    assert!(matches!(hero.position(),Position::Block(2,0,_)));
+    hero.travel(&mut world,-1,0);
+    assert!(matches!(hero.position(),Position::Walking{ to:(1,0),.. }));
+    hero.place(TileAddress { room_id:1,iy:2,ix:2 });
+    hero.travel(&mut world,0,-1);
+    assert_eq!(hero.message(),"YOU CANNOT GO THROUGH THIS WAY");
+    hero.travel(&mut world,0,1);
+    run(&mut hero,&mut world,24);
+    assert!(matches!(hero.position(),Position::Block(1,3,_)));
+
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["H⇒/#"]).unwrap();
+    let at = |ix| TileAddress { room_id:1,iy:0,ix };
+    assert!(world.wire(at(2),at(3)).unwrap());
+    let mut hero = Hero::new(&world,"TEST");
+    hero.place(at(1));
+    run(&mut hero,&mut world,6);
+    assert!(matches!(hero.position(),Position::Block(0,1,_)));
+    assert_eq!(world.get_tile(&at(2)),Some(Tile::Lever { on:false }));
+    assert_eq!(world.get_tile(&at(3)),Some(Tile::Brick));
+}
+
+#[test]
+fn test_plate_under_hero() {
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["H _# "]).unwrap();
+    let at = |ix| TileAddress { room_id:1,iy:0,ix };
+    assert!(world.wire(at(2),at(3)).unwrap());
+    let mut hero = Hero::new(&world,"TEST");
+    for dj in [1,1,1] {
+	hero.travel(&mut world,0,dj);
+	for _ in 0..8 {
+	    hero.tick(&mut world);
+	}
+    }
+    assert!(matches!(hero.position(),Position::Block(0,3,_)));
+    assert_eq!(world.get_tile(&at(3)),Some(Tile::Empty));
+    assert!(world.trigger_at(&at(2)).unwrap().active);
+
+    for dj in [-1,-1] {
+	hero.travel(&mut world,0,dj);
+	for _ in 0..8 {
+	    hero.tick(&mut world);
+	}
+    }
+    assert_eq!(world.get_tile(&at(3)),Some(Tile::Brick));
+    assert!(!world.trigger_at(&at(2)).unwrap().active);
+}
+
+#[test]
+fn test_push() {
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["HB&_#"]).unwrap();
+    world.goals = vec![Goal::CoverAllPlates];
+    let mut hero = Hero::new(&world,"TEST");
+    let row = |world:&World| -> Vec<Tile> {
+	(1..4).map(|ix| world.get_tile(&TileAddress { room_id:1,iy:0,ix }).unwrap()).collect()
+    };
+    hero.travel(&mut world,0,1);
+    assert_eq!(row(&world),[Tile::Empty,Tile::Crate { on_plate:true },Tile::Crate { on_plate:true }]);
+    for _ in 0..8 {
+	hero.tick(&mut world);
+    }
+    assert!(hero.won());
+    hero.travel(&mut world,0,1);
+    assert!(matches!(hero.position(),Position::Block(0,1,_)));
+    assert_eq!(hero.message(),"IT WON'T BUDGE");
+}
+
+#[test]
+fn test_switches() {
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["/H_#$#"]).unwrap();
+    let at = |ix| TileAddress { room_id:1,iy:0,ix };
+    assert!(world.wire(at(0),at(3)).unwrap());
+    assert!(world.wire(at(2),at(5)).unwrap());
+    assert!(world.wire(at(4),at(5)).unwrap());
+    assert!(world.wire(at(1),at(3)).is_err());
+    let mut hero = Hero::new(&world,"TEST");
+
+    hero.travel(&mut world,0,-1);
+    assert_eq!(world.get_tile(&at(0)),Some(Tile::Lever { on:true }));
+    assert_eq!(world.get_tile(&at(3)),Some(Tile::Empty));
+    hero.travel(&mut world,0,-1);
+    assert_eq!(world.get_tile(&at(3)),Some(Tile::Brick));
+
+    hero.travel(&mut world,0,1);
+    hero.tick(&mut world);
+    assert_eq!(world.get_tile(&at(5)),Some(Tile::Empty));
+    for _ in 0..7 {
+	hero.tick(&mut world);
+    }
+    hero.travel(&mut world,0,-1);
+    hero.tick(&mut world);
+    assert_eq!(world.get_tile(&at(5)),Some(Tile::Brick));
+
+    world.set_tile(&at(3),Tile::Empty);
+    for _ in 0..7 {
+	hero.tick(&mut world);
+    }
+    hero.travel(&mut world,0,1);
+    for _ in 0..8 {
+	hero.tick(&mut world);
+    }
+    hero.travel(&mut world,0,1);
+    for _ in 0..8 {
+	hero.tick(&mut world);
+    }
+    hero.travel(&mut world,0,1);
+    assert_eq!(hero.message(),"INSERT A COIN");
+    hero.coins = 1;
+    hero.travel(&mut world,0,1);
+    assert_eq!(world.get_tile(&at(4)),Some(Tile::CoinSlot { paid:true }));
+    assert_eq!(world.get_tile(&at(5)),Some(Tile::Empty));
+    assert_eq!(hero.coins(),0);
+
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["/#H"]).unwrap();
+    assert!(world.wire(at(0),at(1)).unwrap());
+    let mut hero = Hero::new(&world,"TEST");
+    world.set_tile(&at(1),Tile::Empty);
+    world.set_tile(&at(0),Tile::Lever { on:true });
+    hero.place(at(1));
+    hero.travel(&mut world,0,-1);
+    assert_eq!(hero.message(),"IT WON'T BUDGE");
+    assert_eq!(world.get_tile(&at(0)),Some(Tile::Lever { on:true }));
+    assert_eq!(world.get_tile(&at(1)),Some(Tile::Empty));
+}
+
+#[test]
+fn test_scripts() {
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["H K#"]).unwrap();
+    let at = |ix| TileAddress { room_id:1,iy:0,ix };
+    world.scripts.push(ron::de::from_str(r#"(on:EnterRoom(1),once:true,actions:[Say("WELCOME"),Give(Coin)])"#).unwrap());
+    world.scripts.push(Script {
+	on:Event::StepOn(at(1)),
+	once:false,
+	actions:vec![Action::ClearTile(at(3)),Action::Sound(Sounds::Switch)]
+    });
+    world.scripts.push(Script {
+	on:Event::PickUp(Object::KEY),
+	once:false,
+	actions:vec![Action::Take(Object::KEY),Action::Teleport(at(0))]
+    });
+    let mut hero = Hero::new(&world,"TEST");
+    hero.tick(&mut world);
+    assert_eq!(hero.message(),"WELCOME");
+    assert_eq!(hero.coins(),1);
+
+    hero.travel(&mut world,0,1);
+    for _ in 0..8 {
+	hero.tick(&mut world);
+    }
+    assert_eq!(world.get_tile(&at(3)),Some(Tile::Empty));
+
+    hero.travel(&mut world,0,1);
+    assert!(hero.inventory().is_empty());
+    assert!(matches!(hero.position(),Position::Block(0,0,_)));
+    hero.place(TileAddress { room_id:1,iy:0,ix:1 });
+    hero.tick(&mut world);
+    assert_eq!(hero.coins(),1);
+
+    // Scripts are told apart by content, not by position
+    let mut new_world = world.clone();
+    new_world.scripts.reverse();
+    new_world.scripts.insert(0,ron::de::from_str(r#"(on:StepOn((room_id:1,iy:0,ix:1)),once:true,actions:[Give(Coin)])"#).unwrap());
+    hero.relocate(&new_world).unwrap();
+    let mut world = new_world;
+    hero.place(at(0));
+    hero.travel(&mut world,0,1);
+    for _ in 0..8 {
+	hero.tick(&mut world);
+    }
+    assert_eq!(hero.coins(),2);
+}
+
+#[test]
+fn test_creatures() {
+    use crate::creature::{Behaviour,Creature};
+
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["H   "," ## "]).unwrap();
+    world.hazards.enabled = true;
+    let mut bat = Creature::new("BAT",1,(1,3));
+    bat.behaviour = Behaviour::Chase;
+    bat.speed = 4;
+    world.creatures.push(bat);
+    let mut owl = Creature::new("OWL",1,(1,0));
+    owl.contact = Contact::Say("HOO".to_string());
+    world.creatures.push(owl);
+    let mut hero = Hero::new(&world,"TEST");
+
+    hero.travel(&mut world,1,0);
+    assert_eq!(hero.message(),"OWL: HOO");
+    assert!(matches!(hero.position(),Position::Block(0,0,_)));
+
+    for _ in 0..20 {
+	hero.tick(&mut world);
+    }
+    assert_eq!(world.creatures[0].tile(),(0,0));
+    assert_eq!(hero.health(),2);
+    assert_eq!(hero.message(),"BAT HURTS YOU, HEALTH 2");
+    let log : Vec<String> = hero.log().iter().map(|m| m.to_string()).collect();
+    assert_eq!(log,["OWL: HOO","BAT HURTS YOU, HEALTH 2"]);
+}
+
+#[test]
+fn test_sign() {
+    use crate::dialogue::Dialogue;
+
+    let mut world = World::new();
+    world.start = world.add_room(1,"ONE",&["H!!"]).unwrap();
+    let at = TileAddress { room_id:1,iy:0,ix:1 };
+    world.set_sign(at,Dialogue::parse("READ ME\n---\nAGAIN?\n> YES -> 1\n> NO -> END").unwrap()).unwrap();
+    assert!(world.set_sign(TileAddress { room_id:1,iy:0,ix:0 },Dialogue::default()).is_err());
+    let mut hero = Hero::new(&world,"TEST");
+    hero.travel(&mut world,0,1);
+    assert_eq!(hero.conversation().unwrap().page().text,"READ ME");
+    hero.advance();
+    hero.choose(-1);
+    hero.choose(1);
+    hero.advance();
+    assert!(hero.talking());
+    hero.travel(&mut world,0,1);
+    assert_eq!(hero.conversation().unwrap().page,0);
+    hero.advance();
+    hero.choose(1);
+    hero.advance();
+    assert!(!hero.talking());
+}
@@ -0,0 +1,128 @@
+use crate::object::Object;
+
+/// The objects carried by the hero, one of which is selected
+#[derive(Debug,Clone)]
+pub struct Inventory {
+    items:Vec<Object>,
+    capacity:usize,
+    selected:usize
+}
+
+impl Inventory {
+    pub const DEFAULT_CAPACITY : usize = 4;
+    /// As many slots as there are keys to select them
+    pub const MAX_CAPACITY : usize = 9;
+
+    pub fn new(capacity:usize)->Self {
+	Self {
+	    items:Vec::new(),
+	    capacity:capacity.max(1),
+	    selected:0
+	}
+    }
+
+    pub fn capacity(&self)->usize { self.capacity }
+
+    pub fn items(&self)->&[Object] { &self.items }
+
+    pub fn is_empty(&self)->bool { self.items.is_empty() }
+
+    pub fn is_full(&self)->bool { self.items.len() >= self.capacity }
+
+    pub fn contains(&self,obj:Object)->bool {
+	self.items.contains(&obj)
+    }
+
+    /// Index of the selected slot
+    pub fn selected_index(&self)->usize { self.selected }
+
+    pub fn selected(&self)->Option<Object> {
+	self.items.get(self.selected).copied()
+    }
+
+    /// Add an object, which becomes the selected one.  Returns false if
+    /// there is no room left.
+    pub fn add(&mut self,obj:Object)->bool {
+	if self.is_full() {
+	    false
+	} else {
+	    self.items.push(obj);
+	    self.selected = self.items.len() - 1;
+	    true
+	}
+    }
+
+    /// Remove one instance of an object, wherever it is
+    pub fn remove(&mut self,obj:Object)->bool {
+	match self.items.iter().position(|&o| o == obj) {
+	    None => false,
+	    Some(i) => {
+		self.remove_at(i);
+		true
+	    }
+	}
+    }
+
+    pub fn take_selected(&mut self)->Option<Object> {
+	if self.selected < self.items.len() {
+	    Some(self.remove_at(self.selected))
+	} else {
+	    None
+	}
+    }
+
+    fn remove_at(&mut self,i:usize)->Object {
+	let obj = self.items.remove(i);
+	if self.selected > i || self.selected >= self.items.len() {
+	    self.selected = self.selected.saturating_sub(1);
+	}
+	obj
+    }
+
+    pub fn select(&mut self,i:usize) {
+	if i < self.items.len() {
+	    self.selected = i;
+	}
+    }
+
+    /// Select the next (delta = 1) or previous (delta = -1) object
+    pub fn cycle(&mut self,delta:isize) {
+	let n = self.items.len() as isize;
+	if n > 0 {
+	    self.selected = (self.selected as isize + delta).rem_euclid(n) as usize;
+	}
+    }
+
+    /// Change the capacity, dropping the objects that no longer fit.
+    /// Returns the dropped objects.
+    pub fn set_capacity(&mut self,capacity:usize)->Vec<Object> {
+	self.capacity = capacity.max(1);
+	let lost = self.items.split_off(self.items.len().min(self.capacity));
+	if self.selected >= self.items.len() {
+	    self.selected = self.items.len().saturating_sub(1);
+	}
+	lost
+    }
+}
+
+#[test]
+fn test_inventory() {
+    let mut inv = Inventory::new(3);
+    assert!(inv.add(Object::KEY));
+    assert!(inv.add(Object::TOY_CAR));
+    assert!(inv.add(Object::KEY));
+    assert!(!inv.add(Object::CARROT));
+    assert_eq!(inv.selected(),Some(Object::KEY));
+    inv.cycle(1);
+    assert_eq!(inv.selected_index(),0);
+    inv.cycle(-1);
+    assert_eq!(inv.selected_index(),2);
+    inv.select(1);
+    assert!(inv.remove(Object::KEY));
+    assert_eq!(inv.items(),&[Object::TOY_CAR,Object::KEY]);
+    assert_eq!(inv.selected(),Some(Object::TOY_CAR));
+    assert_eq!(inv.take_selected(),Some(Object::TOY_CAR));
+    assert_eq!(inv.selected(),Some(Object::KEY));
+    assert_eq!(inv.set_capacity(0),vec![]);
+    assert_eq!(inv.capacity(),1);
+}
@@ -1,7 +1,22 @@
 pub mod a2;
+pub mod creature;
+pub mod dialogue;
+pub mod facing;
+pub mod goal;
+pub mod hazards;
+pub mod hero;
+pub mod inventory;
+pub mod link;
 pub mod mini_rng;
 pub mod object;
+pub mod position;
 pub mod ptr;
 pub mod room;
+pub mod script;
+pub mod sketch;
+pub mod sounds;
+pub mod text;
 pub mod tiles;
+pub mod trigger;
+pub mod vortex;
 pub mod world;
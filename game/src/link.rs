@@ -0,0 +1,84 @@
+use anyhow::{
+    Result
+};
+
+use std::io::Write;
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::world::{
+    TileAddress,
+    World
+};
+
+/// Address the player listens on for the editor, unless overridden
+/// by the MZG_LINK environment variable
+pub const DEFAULT_ADDRESS : &str = "127.0.0.1:7357";
+
+/// Commands sent by the editor to a running player.  They travel over
+/// the link as RON, one command per line.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub enum Command {
+    /// Replace the world being played
+    World(Box<World>),
+    /// Move the hero to the given tile
+    Teleport(TileAddress)
+}
+
+pub fn address()->String {
+    std::env::var("MZG_LINK").unwrap_or_else(|_| DEFAULT_ADDRESS.to_string())
+}
+
+impl Command {
+    pub fn send<W:Write>(&self,w:&mut W)->Result<()> {
+	let mut u = ron::ser::to_string(self)?;
+	u.push('\n');
+	w.write_all(u.as_bytes())?;
+	w.flush()?;
+	Ok(())
+    }
+
+    /// Read a command, checking the world it may carry as if it had
+    /// been loaded from a file
+    pub fn parse(line:&str)->Result<Self> {
+	let mut command : Self = ron::de::from_str(line)?;
+	if let Command::World(world) = &mut command {
+	    world.validate()?;
+	}
+	Ok(command)
+    }
+}
+
+#[test]
+fn test_command_round_trip() {
+    use crate::{
+	creature::Creature,
+	ptr::Make
+    };
+
+    let mut world = World::new();
+    world.start = world.add_room(3,"NAME\nWITH NEWLINE",&["#H0#"]).unwrap();
+    let mut buf = Vec::new();
+    Command::World(Box::new(world)).send(&mut buf).unwrap();
+    Command::Teleport(TileAddress { room_id:3,iy:0,ix:1 }).send(&mut buf).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+    let lines : Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(),2);
+    match Command::parse(lines[0]).unwrap() {
+	Command::World(w) => assert_eq!(w.get_room(3).yank().name,"NAME\nWITH NEWLINE"),
+	_ => panic!("Expected a world")
+    }
+    assert!(matches!(Command::parse(lines[1]).unwrap(),
+		     Command::Teleport(TileAddress { room_id:3,iy:0,ix:1 })));
+
+    let mut world = World::new();
+    world.start = world.add_room(3,"THREE",&["#H0#"]).unwrap();
+    world.creatures.push(Creature::new("BAT",4,(0,0)));
+    let mut buf = Vec::new();
+    Command::World(Box::new(world)).send(&mut buf).unwrap();
+    let e = Command::parse(String::from_utf8(buf).unwrap().trim_end()).unwrap_err();
+    assert_eq!(e.to_string(),"Creature \"BAT\" is in missing room 4");
+}
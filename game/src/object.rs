@@ -8,8 +8,24 @@ use serde::{
     Serialize
 };
 
-#[derive(PartialEq,Copy,Clone,Debug,Serialize,Deserialize)]
-pub enum Object {
+use crate::{
+    mini_rng::MiniRNG,
+    room::Room,
+    sounds::Sounds,
+    tiles::Tile
+};
+
+/// An object, known by the character standing for it in room maps.
+/// What it looks like and what it does is given by the object types
+/// of the world.
+#[derive(PartialEq,Eq,PartialOrd,Ord,Copy,Clone,Debug,Serialize,Deserialize)]
+#[serde(from = "Repr",into = "Repr")]
+pub struct Object(pub char);
+
+/// How objects are written in RON files, the original objects keeping
+/// the names they had when they were a closed set
+#[derive(Serialize,Deserialize)]
+enum Repr {
     Coin,
     Key,
     ToyCar,
@@ -18,64 +34,165 @@ pub enum Object {
     Tomato,
     Eggplant,
     Banana,
-    Carrot
+    Carrot,
+    Custom(char)
 }
 
-impl Object {
-    pub fn is_food(self)->bool {
-	match self {
-	    Object::IceCream |
-	    Object::Carrot |
-	    Object::Tomato |
-	    Object::Eggplant |
-	    Object::Banana => true,
-	    _ => false
+impl From<Repr> for Object {
+    fn from(r:Repr)->Self {
+	match r {
+	    Repr::Coin => Object::COIN,
+	    Repr::Key => Object::KEY,
+	    Repr::ToyCar => Object::TOY_CAR,
+	    Repr::SquaresAndTriangles => Object::SQUARES_AND_TRIANGLES,
+	    Repr::IceCream => Object::ICE_CREAM,
+	    Repr::Tomato => Object::TOMATO,
+	    Repr::Eggplant => Object::EGGPLANT,
+	    Repr::Banana => Object::BANANA,
+	    Repr::Carrot => Object::CARROT,
+	    Repr::Custom(c) => Object(c)
 	}
     }
-    pub fn is_consumable(self)->bool {
-	match self {
-	    Object::IceCream |
-	    Object::Carrot |
-	    Object::Tomato |
-	    Object::Eggplant |
-	    Object::Banana |
-	    Object::Coin => true,
-	    _ => false
+}
+
+impl From<Object> for Repr {
+    fn from(obj:Object)->Self {
+	match obj {
+	    Object::COIN => Repr::Coin,
+	    Object::KEY => Repr::Key,
+	    Object::TOY_CAR => Repr::ToyCar,
+	    Object::SQUARES_AND_TRIANGLES => Repr::SquaresAndTriangles,
+	    Object::ICE_CREAM => Repr::IceCream,
+	    Object::TOMATO => Repr::Tomato,
+	    Object::EGGPLANT => Repr::Eggplant,
+	    Object::BANANA => Repr::Banana,
+	    Object::CARROT => Repr::Carrot,
+	    Object(c) => Repr::Custom(c)
 	}
     }
-    pub fn is_fattening(self)->bool {
-	match self {
-	    Object::IceCream => true,
-	    _ => false
+}
+
+impl Object {
+    pub const COIN : Object = Object('C');
+    pub const KEY : Object = Object('K');
+    pub const TOY_CAR : Object = Object('T');
+    pub const SQUARES_AND_TRIANGLES : Object = Object('S');
+    pub const ICE_CREAM : Object = Object('I');
+    pub const TOMATO : Object = Object('t');
+    pub const EGGPLANT : Object = Object('e');
+    pub const BANANA : Object = Object('b');
+    pub const CARROT : Object = Object('c');
+
+    /// The object of a room map character that is not a tile.  Any
+    /// letter may stand for an object.
+    pub fn from_char(c:char)->Result<Self> {
+	if c.is_ascii_alphabetic() {
+	    Ok(Object(c))
+	} else {
+	    bail!("Invalid object {c:?}")
 	}
     }
-    pub fn name(self)->&'static str {
-	match self {
-	    Object::Coin => "A COIN",
-	    Object::IceCream => "SOME ICE CREAM",
-	    Object::Key => "A KEY",
-	    Object::ToyCar => "A TOY CAR",
-	    Object::SquaresAndTriangles => "SOME SQUARES AND TRIANGLES",
-	    Object::Tomato => "A TOMATO",
-	    Object::Eggplant => "AN EGGPLANT",
-	    Object::Banana => "A BANANA",
-	    Object::Carrot => "A CARROT",
+}
+
+/// The properties of an object, as declared by a world
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct ObjectType {
+    /// The character standing for the object in room maps
+    pub code:char,
+    pub name:String,
+    /// Row and column of the sprite in the tile sheet
+    pub sprite:(usize,usize),
+    /// Eaten when picked up, counting as a food
+    #[serde(default)]
+    pub food:bool,
+    /// Makes the hero fat when eaten
+    #[serde(default)]
+    pub fattening:bool,
+    /// Counted as a coin when picked up
+    #[serde(default)]
+    pub coin:bool,
+    /// Used up when picked up rather than carried
+    #[serde(default)]
+    pub consumable:bool,
+    /// Can lock doors, the editor offering only keys for them
+    #[serde(default)]
+    pub key:bool,
+    #[serde(default = "default_sound")]
+    pub sound:Sounds,
+    /// Points scored when picking it up
+    #[serde(default)]
+    pub score:usize
+}
+
+fn default_sound()->Sounds {
+    Sounds::PickUpObject
+}
+
+impl ObjectType {
+    pub fn new(code:char,name:&str,sprite:(usize,usize))->Self {
+	Self {
+	    code,
+	    name:name.to_string(),
+	    sprite,
+	    food:false,
+	    fattening:false,
+	    coin:false,
+	    consumable:false,
+	    key:false,
+	    sound:default_sound(),
+	    score:0
 	}
     }
-    pub fn from_char(c:char)->Result<Self> {
-	let obj = 
-	    match c {
-		'K' => Self::Key,
-		'T' => Self::ToyCar,
-		'I' => Self::IceCream,
-		'C' => Self::Coin,
-		'S' => Self::SquaresAndTriangles,
-		'c' => Self::Carrot,
-		't' => Self::Tomato,
-		'e' => Self::Eggplant,
-		'b' => Self::Banana,
-		_ => bail!("Invalid object {c:?}")
-	    };
-	Ok(obj)
+
+    /// Stands for objects the world does not declare
+    pub fn unknown(obj:Object)->Self {
+	Self::new(obj.0,&format!("SOMETHING ({})",obj.0),(2,3))
+    }
+
+    /// Can the character stand for an object in room maps, rather
+    /// than for a built-in tile?
+    pub fn is_free_code(code:char)->bool {
+	matches!(Room::char_tile(code,&mut MiniRNG::new(1)),Some(Tile::Object(_)))
+    }
+
+    pub fn object(&self)->Object {
+	Object(self.code)
+    }
+
+    /// Does the hero keep the object in the inventory?
+    pub fn is_carried(&self)->bool {
+	!(self.food || self.coin || self.consumable)
     }
+
+    /// The objects of worlds that do not declare any
+    pub fn defaults()->Vec<Self> {
+	let food = |code,name,sprite| Self {
+	    food:true,
+	    consumable:true,
+	    sound:Sounds::EatFood,
+	    ..Self::new(code,name,sprite)
+	};
+	vec![
+	    Self { coin:true,consumable:true,sound:Sounds::PickUpCoin,..Self::new('C',"A COIN",(0,1)) },
+	    Self { key:true,..Self::new('K',"A KEY",(0,4)) },
+	    Self::new('T',"A TOY CAR",(0,5)),
+	    Self::new('S',"SOME SQUARES AND TRIANGLES",(0,6)),
+	    Self { fattening:true,..food('I',"SOME ICE CREAM",(0,3)) },
+	    food('t',"A TOMATO",(1,5)),
+	    food('e',"AN EGGPLANT",(1,6)),
+	    food('b',"A BANANA",(1,7)),
+	    food('c',"A CARROT",(1,8))
+	]
+    }
+}
+
+#[test]
+fn test_object_repr() {
+    let objs = vec![Object::KEY,Object('g')];
+    let u = ron::ser::to_string(&objs).unwrap();
+    assert_eq!(u,"[Key,Custom('g')]");
+    assert_eq!(ron::de::from_str::<Vec<Object>>(&u).unwrap(),objs);
+    assert!(Object::from_char('#').is_err());
+    assert!(ObjectType::is_free_code('g'));
+    assert!(!ObjectType::is_free_code('R'));
 }
@@ -19,4 +19,16 @@ impl Position {
 		Facing::from_deltas(i1 as isize - i0 as isize,j1 as isize - j0 as isize)
 	}
     }
+
+    /// Row and column, fractional while walking
+    pub fn coords(self)->(f32,f32) {
+	match self {
+	    Position::Block(i,j,_) => (i as f32,j as f32),
+	    Position::Walking{ from:(i0,j0),to:(i1,j1),step,total } => {
+		let t = step as f32/total as f32;
+		(i0 as f32 + t*(i1 as f32 - i0 as f32),
+		 j0 as f32 + t*(j1 as f32 - j0 as f32))
+	    }
+	}
+    }
 }
@@ -1,4 +1,10 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    fmt::{
+	Display,
+	Formatter
+    }
+};
 
 use serde::{
     Deserialize,
@@ -6,6 +12,7 @@ use serde::{
 };
 
 use crate::{
+    facing::Facing,
     mini_rng::MiniRNG,
     object::Object,
     a2::A2,
@@ -109,6 +116,28 @@ impl Room {
 	}
     }
 
+    /// A copy of this room under a new id, with all doors unconnected
+    pub fn duplicate(&self,id:usize)->Self {
+	let mut room = self.clone();
+	room.id = id;
+	room.name = format!("{} (copy)",self.name);
+	for &(iy,ix) in self.doors.values() {
+	    if let Tile::Door(d) = &mut room.map[[iy,ix]] {
+		d.target = None;
+	    }
+	}
+	room
+    }
+
+    /// Rewrite the room references of door targets using `f`
+    pub fn retarget<F:Fn(usize)->usize>(&mut self,f:F) {
+	for &(iy,ix) in self.doors.values() {
+	    if let Tile::Door(Door { target:Some(t), .. }) = &mut self.map[[iy,ix]] {
+		t.room = f(t.room);
+	    }
+	}
+    }
+
     pub fn empty(id:usize,rows:usize,cols:usize)->Self {
 	let map = A2::new((rows as isize,cols as isize),Tile::Empty);
 	Self {
@@ -121,58 +150,113 @@ impl Room {
 	}
     }
 
-    pub fn new(id:usize,name:&str,a:&[&str])->(Self,Option<TileAddress>) {
+    /// The tile denoted by a character of the world file format, where
+    /// 'H' marks the starting position over an empty tile
+    pub fn char_tile(c:char,rng:&mut MiniRNG)->Option<Tile> {
+	let t =
+	    match c {
+		' ' | 'H' => Tile::Empty,
+		'#' => Tile::Brick,
+		'R' => Tile::Rainbow,
+		'~' => Tile::Water(Periodic::new(8,8)),
+		'@' => Tile::Vortex,
+		'B' => Tile::Crate { on_plate:false },
+		'&' => Tile::Crate { on_plate:true },
+		'_' => Tile::Plate,
+		'/' => Tile::Lever { on:false },
+		'\\' => Tile::Lever { on:true },
+		'$' => Tile::CoinSlot { paid:false },
+		'!' => Tile::Sign,
+		'▒' => Tile::Ice,
+		'⇒' => Tile::Conveyor(Facing::Right),
+		'⇐' => Tile::Conveyor(Facing::Left),
+		'⇑' => Tile::Conveyor(Facing::Up),
+		'⇓' => Tile::Conveyor(Facing::Down),
+		'→' => Tile::OneWay(Facing::Right),
+		'←' => Tile::OneWay(Facing::Left),
+		'↑' => Tile::OneWay(Facing::Up),
+		'↓' => Tile::OneWay(Facing::Down),
+		'.' => Tile::Grass,
+		'%' => Tile::Dirt,
+		'*' => Tile::PyramidStone,
+		'W' => Tile::Window,
+		'F' => Tile::Fire(Periodic::new(3,2)),
+		'q' => Tile::MetalRamp(Corner::NW),
+		'w' => Tile::MetalRamp(Corner::NE),
+		'a' => Tile::MetalRamp(Corner::SW),
+		's' => Tile::MetalRamp(Corner::SE),
+		'm' => Tile::Metal,
+		'A' => Tile::Alien,
+		'x' => Tile::MetalFoot,
+		'^' => Tile::Sky(Random::new(rng.sample_u32(20))),
+		'0'..='9' => {
+		    let x = c.to_digit(10).unwrap() as usize;
+		    Tile::Door(Door{ id:x,target:None,key:None,
+				     locked:false,requires:None })
+		},
+		_ => Tile::Object(Object::from_char(c).ok()?)
+	    };
+	Some(t)
+    }
+
+    /// The tile denoted by a character, which may be the code of one
+    /// of the given tile types
+    pub fn declared_tile(c:char,types:&[TileType],rng:&mut MiniRNG)->Option<Tile> {
+	Self::char_tile(c,rng)
+	    .or_else(|| types.iter().find(|tt| tt.code == c).map(TileType::tile))
+    }
+
+    /// Make a room from rows of world file characters, rows shorter
+    /// than the longest being padded with `pad` if given
+    pub fn parse(id:usize,name:&str,a:&[&str],pad:Option<Tile>)
+		 ->Result<(Self,Option<TileAddress>),ParseError> {
+	Self::parse_with(id,name,a,pad,&[])
+    }
+
+    /// Make a room as `parse` does, also knowing the given tile types
+    pub fn parse_with(id:usize,name:&str,a:&[&str],pad:Option<Tile>,types:&[TileType])
+		      ->Result<(Self,Option<TileAddress>),ParseError> {
 	let mut rng = MiniRNG::new(1);
-	// let a : Vec<&str> = descr.split('\n').collect();
 	let rows = a.len();
-	let cols = a[0].len();
+	let cols = a.iter().map(|u| u.chars().count()).max().unwrap_or(0);
+	if rows == 0 || cols == 0 {
+	    return Err(ParseError::Empty);
+	}
 	let mut map = A2::new((rows as isize,cols as isize),Tile::Empty);
 	let mut doors = BTreeMap::new();
 	let mut start = None;
 	for i in 0..rows {
-	    // println!("ROW {:2} [{}]",i,a[i]);
+	    let mut len = 0;
 	    for (j,c) in a[i].chars().enumerate() {
 		let t =
-		    match c {
-			' ' => Tile::Empty,
-			'H' => {
-			    start = Some(TileAddress { room_id:id,
-						       iy:i,ix:j });
-			    Tile::Empty
-			},
-			'#' => Tile::Brick,
-			'R' => Tile::Rainbow,
-			'~' => Tile::Water(Periodic::new(8,8)),
-			'@' => Tile::Vortex,
-			'.' => Tile::Grass,
-			'%' => Tile::Dirt,
-			'*' => Tile::PyramidStone,
-			'W' => Tile::Window,
-			'F' => Tile::Fire(Periodic::new(3,2)),
-			'q' => Tile::MetalRamp(Corner::NW),
-			'w' => Tile::MetalRamp(Corner::NE),
-			'a' => Tile::MetalRamp(Corner::SW),
-			's' => Tile::MetalRamp(Corner::SE),
-			'm' => Tile::Metal,
-			'A' => Tile::Alien,
-			'x' => Tile::MetalFoot,
-			'^' => Tile::Sky(Random::new(rng.sample_u32(20))),
-			'0'..='9' => {
-			    let x = c.to_digit(10).unwrap() as usize;
-			    doors.insert(x,(i,j));
-			    Tile::Door(Door{ id:x,target:None,key:None,
-					     locked:false })
-			},
-			_ => if let Ok(obj) = Object::from_char(c) {
-			    Tile::Object(obj)
-			} else {
-			    panic!("Unsupported tile {}",c)
-			}
+		    match Self::declared_tile(c,types,&mut rng) {
+			Some(t) => t,
+			None => return Err(ParseError::UnknownChar { row:i,col:j,c })
 		    };
+		match t {
+		    Tile::Door(d) => {
+			doors.insert(d.id,(i,j));
+		    },
+		    _ => if c == 'H' {
+			start = Some(TileAddress { room_id:id,
+						   iy:i,ix:j });
+		    }
+		}
 		map[[i,j]] = t;
+		len += 1;
+	    }
+	    if len < cols {
+		match pad {
+		    None => return Err(ParseError::Ragged { row:i,len,expected:cols }),
+		    Some(t) => {
+			for j in len..cols {
+			    map[[i,j]] = t;
+			}
+		    }
+		}
 	    }
 	}
-	(Self {
+	Ok((Self {
 	    id,
 	    rows,
 	    cols,
@@ -180,6 +264,56 @@ impl Room {
 	    doors,
 	    name:name.to_string()
 	},
-	 start)
+	    start))
+    }
+
+    /// Check that a room read from elsewhere is consistent, and rebuild
+    /// its door index
+    pub fn validate(&mut self)->Result<(),ParseError> {
+	let (d0,d1) = self.map.dims();
+	if !self.map.is_consistent() || d0 != self.rows as isize || d1 != self.cols as isize {
+	    return Err(ParseError::BadDimensions);
+	}
+	self.reindex_doors();
+	Ok(())
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum ParseError {
+    Empty,
+    UnknownChar { row:usize,col:usize,c:char },
+    Ragged { row:usize,len:usize,expected:usize },
+    BadDimensions
+}
+
+impl Display for ParseError {
+    fn fmt(&self,f:&mut Formatter<'_>)->std::result::Result<(),std::fmt::Error> {
+	match self {
+	    ParseError::Empty => write!(f,"Empty room"),
+	    ParseError::UnknownChar { row,col,c } =>
+		write!(f,"Unsupported character {:?} at row {} column {}",c,row,col),
+	    ParseError::Ragged { row,len,expected } =>
+		write!(f,"Row {} has {} columns instead of {}",row,len,expected),
+	    ParseError::BadDimensions => write!(f,"Map dimensions are inconsistent")
+	}
     }
 }
+
+impl std::error::Error for ParseError { }
+
+#[test]
+fn test_parse() {
+    let (room,start) = Room::parse(1,"R",&["#H#","#","##0##"],Some(Tile::Brick)).unwrap();
+    assert_eq!(room.dims(),(3,5));
+    assert_eq!(room.map[[1usize,4usize]],Tile::Brick);
+    assert_eq!(room.map[[0usize,1usize]],Tile::Empty);
+    assert_eq!(start,Some(TileAddress { room_id:1,iy:0,ix:1 }));
+
+    assert_eq!(Room::parse(1,"R",&["##","#"],None).unwrap_err(),
+	       ParseError::Ragged { row:1,len:1,expected:2 });
+    assert_eq!(Room::parse(1,"R",&["##","#?"],None).unwrap_err(),
+	       ParseError::UnknownChar { row:1,col:1,c:'?' });
+    assert_eq!(Room::parse(1,"R",&[],None).unwrap_err(),ParseError::Empty);
+    assert_eq!(Room::parse(1,"R",&[""],None).unwrap_err(),ParseError::Empty);
+}
@@ -0,0 +1,111 @@
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    object::Object,
+    sounds::Sounds,
+    tiles::Tile,
+    world::TileAddress
+};
+
+/// Something the hero does that a script can react to
+#[derive(Copy,Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Event {
+    /// Enter the given room, including at the start of the game
+    EnterRoom(usize),
+    /// Finish a step onto the given tile
+    StepOn(TileAddress),
+    /// Pick up an object, anywhere
+    PickUp(Object),
+    /// Unlock the door at the given tile
+    Unlock(TileAddress)
+}
+
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Action {
+    Say(String),
+    SetTile(TileAddress,Tile),
+    /// Make a tile empty
+    ClearTile(TileAddress),
+    /// Put an object in the inventory, if there is room
+    Give(Object),
+    /// Remove an object from the inventory, if it is there
+    Take(Object),
+    Sound(Sounds),
+    Teleport(TileAddress)
+}
+
+/// Actions to run when an event happens, written in RON as in
+/// `(on:EnterRoom(2),once:true,actions:[Say("WELCOME")])`
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Script {
+    pub on:Event,
+    /// Run only the first time the event happens
+    #[serde(default)]
+    pub once:bool,
+    pub actions:Vec<Action>
+}
+
+impl Script {
+    /// The tiles the script refers to
+    pub fn addresses(&self)->Vec<&TileAddress> {
+	let mut v = Vec::new();
+	match &self.on {
+	    Event::StepOn(ta) | Event::Unlock(ta) => v.push(ta),
+	    _ => ()
+	}
+	for action in self.actions.iter() {
+	    match action {
+		Action::SetTile(ta,_) | Action::ClearTile(ta) | Action::Teleport(ta) => v.push(ta),
+		_ => ()
+	    }
+	}
+	v
+    }
+
+    /// The tiles the script refers to, to be rewritten
+    fn addresses_mut(&mut self)->Vec<&mut TileAddress> {
+	let mut v = Vec::new();
+	match &mut self.on {
+	    Event::StepOn(ta) | Event::Unlock(ta) => v.push(ta),
+	    _ => ()
+	}
+	for action in self.actions.iter_mut() {
+	    match action {
+		Action::SetTile(ta,_) | Action::ClearTile(ta) | Action::Teleport(ta) => v.push(ta),
+		_ => ()
+	    }
+	}
+	v
+    }
+
+    /// Does the script refer to the given room?
+    pub fn mentions(&self,room:usize)->bool {
+	self.addresses().iter().any(|ta| ta.room_id == room) ||
+	    self.on == Event::EnterRoom(room)
+    }
+
+    /// Rewrite the room references of the script using `f`
+    pub fn renumber<F:Fn(usize)->usize>(&mut self,f:F) {
+	if let Event::EnterRoom(room) = &mut self.on {
+	    *room = f(*room);
+	}
+	for ta in self.addresses_mut() {
+	    ta.room_id = f(ta.room_id);
+	}
+    }
+
+    /// Move the tiles of the script using `f`.  Returns false if one
+    /// of them is dropped, in which case the script should go too.
+    pub fn relocate<F:Fn(TileAddress)->Option<TileAddress>>(&mut self,f:F)->bool {
+	for ta in self.addresses_mut() {
+	    match f(*ta) {
+		Some(new) => *ta = new,
+		None => return false
+	    }
+	}
+	true
+    }
+}
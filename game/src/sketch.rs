@@ -0,0 +1,165 @@
+use anyhow::{
+    bail,
+    Result
+};
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path
+};
+
+use crate::{
+    mini_rng::MiniRNG,
+    room::Room,
+    tiles::{
+	Tile,
+	TileType
+    },
+    world::TileAddress
+};
+
+/// Maps colours to world file characters.  A palette file has one
+/// `RRGGBB C` line per colour, where C is a character or `space`;
+/// empty lines and lines starting with `//` are ignored.
+#[derive(Clone,Debug,Default)]
+pub struct Palette {
+    colors:BTreeMap<[u8;3],char>
+}
+
+impl Palette {
+    pub fn parse(text:&str)->Result<Self> {
+	let mut colors = BTreeMap::new();
+	for (i,line) in text.lines().enumerate() {
+	    let line = line.trim();
+	    if line.is_empty() || line.starts_with("//") {
+		continue;
+	    }
+	    let words : Vec<&str> = line.split_whitespace().collect();
+	    let (hex,c) =
+		match words[..] {
+		    [hex,"space"] => (hex,' '),
+		    [hex,c] if c.chars().count() == 1 => (hex,c.chars().next().unwrap()),
+		    _ => bail!("Invalid palette line {}: {:?}",i + 1,line)
+		};
+	    let hex = hex.trim_start_matches('#');
+	    let rgb = u32::from_str_radix(hex,16);
+	    match rgb {
+		Ok(rgb) if hex.len() == 6 => {
+		    colors.insert([(rgb >> 16) as u8,(rgb >> 8) as u8,rgb as u8],c);
+		},
+		_ => bail!("Invalid colour {:?} at palette line {}",hex,i + 1)
+	    }
+	}
+	Ok(Self { colors })
+    }
+
+    pub fn load<P:AsRef<Path>>(path:P)->Result<Self> {
+	Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn get(&self,rgb:[u8;3])->Option<char> {
+	self.colors.get(&rgb).copied()
+    }
+}
+
+/// Collects what could not be converted, for reporting
+#[derive(Default)]
+struct Unknown {
+    what:BTreeMap<String,(usize,(usize,usize))>
+}
+
+impl Unknown {
+    fn add(&mut self,what:String,iy:usize,ix:usize) {
+	self.what.entry(what).or_insert((0,(iy,ix))).0 += 1;
+    }
+
+    fn check(&self,kind:&str)->Result<()> {
+	if self.what.is_empty() {
+	    return Ok(());
+	}
+	let items : Vec<String> = self.what.iter()
+	    .map(|(what,&(n,(iy,ix)))|
+		 format!("{} ({} times, first at row {} column {})",what,n,iy,ix))
+	    .collect();
+	bail!("Unknown {}: {}",kind,items.join(", "))
+    }
+}
+
+/// The rows of a text sketch, a plain text grid using the characters
+/// of the world file format
+pub fn text_rows(text:&str)->Vec<String> {
+    let mut rows : Vec<String> = text.lines()
+	.map(|l| l.trim_end_matches('\r').to_string())
+	.collect();
+    while rows.last().map(|r| r.trim().is_empty()).unwrap_or(false) {
+	rows.pop();
+    }
+    rows
+}
+
+/// The rows of an image of the given size, `pixel(iy,ix)` giving the
+/// colour of each pixel
+pub fn image_rows<F:Fn(usize,usize)->[u8;3]>(rows:usize,cols:usize,pixel:F,palette:&Palette)
+					     ->Result<Vec<String>> {
+    let mut unknown = Unknown::default();
+    let mut a = Vec::new();
+    for iy in 0..rows {
+	let mut row = String::new();
+	for ix in 0..cols {
+	    let [r,g,b] = pixel(iy,ix);
+	    match palette.get([r,g,b]) {
+		Some(c) => row.push(c),
+		None => {
+		    unknown.add(format!("#{:02x}{:02x}{:02x}",r,g,b),iy,ix);
+		    row.push(' ');
+		}
+	    }
+	}
+	a.push(row);
+    }
+    unknown.check("colours")?;
+    Ok(a)
+}
+
+/// Make a room from rows of world file characters, which may be the
+/// codes of the given tile types.  Short rows are padded with empty
+/// tiles.
+pub fn room_from_rows(id:usize,name:&str,rows:&[String],types:&[TileType])
+		      ->Result<(Room,Option<TileAddress>)> {
+    let mut rng = MiniRNG::new(1);
+    let mut unknown = Unknown::default();
+    for (iy,row) in rows.iter().enumerate() {
+	for (ix,c) in row.chars().enumerate() {
+	    if Room::declared_tile(c,types,&mut rng).is_none() {
+		unknown.add(format!("{:?}",c),iy,ix);
+	    }
+	}
+    }
+    unknown.check("characters")?;
+    let rows : Vec<&str> = rows.iter().map(|r| r.as_str()).collect();
+    Ok(Room::parse_with(id,name,&rows,Some(Tile::Empty),types)?)
+}
+
+#[test]
+fn test_text_sketch() {
+    let rows = text_rows("####\n#H 0#\n#\n\n");
+    assert_eq!(rows.len(),3);
+    let (room,start) = room_from_rows(7,"SKETCH",&rows,&[]).unwrap();
+    assert_eq!(room.dims(),(3,5));
+    assert_eq!(room.locate_door(0),Some((1,3)));
+    assert_eq!(start,Some(TileAddress { room_id:7,iy:1,ix:1 }));
+
+    let e = room_from_rows(7,"BAD",&text_rows("#?#\n#??"),&[]).unwrap_err();
+    assert_eq!(e.to_string(),"Unknown characters: '?' (3 times, first at row 0 column 1)");
+}
+
+#[test]
+fn test_image_sketch() {
+    let palette = Palette::parse("// test\nff0000 #\n000000 space\n00ff00 H\n").unwrap();
+    let img = [[[255,0,0],[0,0,0]],[[0,255,0],[1,2,3]]];
+    let e = image_rows(2,2,|iy,ix| img[iy][ix],&palette).unwrap_err();
+    assert!(e.to_string().contains("#010203"));
+    let rows = image_rows(2,1,|iy,ix| img[iy][ix],&palette).unwrap();
+    assert_eq!(rows,vec!["#".to_string(),"H".to_string()]);
+}
@@ -0,0 +1,20 @@
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq,PartialOrd,Ord,Serialize,Deserialize)]
+pub enum Sounds {
+    Walk,
+    BlockedAgainstWall,
+    PickUpObject,
+    GoThroughDoor,
+    EatFood,
+    PickUpCoin,
+    Teleport,
+    Hurt,
+    Die,
+    Splash,
+    Push,
+    Switch
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{
+	Display,
+	Formatter
+    },
+    path::Path
+};
+
+use anyhow::{
+    bail,
+    Result
+};
+
+use crate::object::{
+    Object,
+    ObjectType
+};
+
+/// Something filling a `{n}` placeholder of a message
+#[derive(Clone,Debug,PartialEq)]
+pub enum Arg {
+    Text(String),
+    Number(usize),
+    /// An object with its name in the world
+    Object(Object,String),
+    Message(Box<Message>)
+}
+
+impl From<usize> for Arg {
+    fn from(n:usize)->Self { Arg::Number(n) }
+}
+
+impl From<&ObjectType> for Arg {
+    fn from(ot:&ObjectType)->Self { Arg::Object(ot.object(),ot.name.clone()) }
+}
+
+impl From<&str> for Arg {
+    fn from(u:&str)->Self { Arg::Text(u.to_string()) }
+}
+
+impl From<Message> for Arg {
+    fn from(msg:Message)->Self { Arg::Message(Box::new(msg)) }
+}
+
+/// Text for the player, given by the key of its template in the
+/// message catalog and the values of its placeholders
+#[derive(Clone,Debug,PartialEq)]
+pub struct Message {
+    pub key:&'static str,
+    pub args:Vec<Arg>
+}
+
+impl Message {
+    pub fn new(key:&'static str,args:Vec<Arg>)->Self {
+	Self { key,args }
+    }
+
+    /// Text coming from the world, shown as is
+    pub fn verbatim(text:&str)->Self {
+	Self::new("verbatim",vec![text.into()])
+    }
+}
+
+/// Builds a message from a key and arguments convertible to `Arg`
+#[macro_export]
+macro_rules! msg {
+    ($key:expr) => { $crate::text::Message::new($key,Vec::new()) };
+    ($key:expr,$($arg:expr),+) => {
+	$crate::text::Message::new($key,vec![$($crate::text::Arg::from($arg)),+])
+    };
+}
+
+/// The built-in English messages, which locale files override
+const ENGLISH : &[(&str,&str)] = &[
+    ("verbatim","{0}"),
+    ("world_changed","THE WORLD HAS CHANGED AROUND YOU"),
+    ("carry_nothing","YOU CARRY NOTHING"),
+    ("hold","YOU HOLD {0}"),
+    ("eat","YOU EAT {0}"),
+    ("eat_fat","YOU EAT {0} AND GET FAT.  WALK TO LOSE WEIGHT"),
+    ("take","YOU TAKE {0}"),
+    ("drop","YOU DROP {0}"),
+    ("cannot_drop_over","YOU CANNOT DROP {0} OVER THAT"),
+    ("cannot_drop_edge","YOU CANNOT DROP THINGS OVER THE EDGE"),
+    ("cannot_drop_walking","YOU CANNOT DROP THINGS WHILE WALKING"),
+    ("cannot_drop_boat","YOU CANNOT DROP {0} ON THE WATER"),
+    ("hands_full","YOU CANNOT CARRY {0}, YOUR HANDS ARE FULL"),
+    ("rainbow","YOU REACHED THE RAINBOW"),
+    ("won","CONGRATULATIONS {0} YOU WON!"),
+    ("time_up","TIME IS UP, YOU CAN NO LONGER WIN"),
+    ("door_open","YOU OPEN THE DOOR"),
+    ("door_unlock","YOU UNLOCK THE DOOR WITH {0}"),
+    ("door_forever","THE DOOR IS FOREVER LOCKED"),
+    ("door_remote","THE DOOR IS OPERATED FROM ELSEWHERE"),
+    ("door_broken","THIS DOOR HAS NOT BEEN INSTALLED CORRECTLY"),
+    ("door_nowhere","THIS DOOR LEADS NOWHERE"),
+    ("need","YOU NEED {0}"),
+    ("toll_short","THE TOLL IS {0} COINS, YOU HAVE {1}"),
+    ("coins_short","YOU NEED {0} COINS TO PASS, YOU HAVE {1}"),
+    ("foods_short","YOU MUST EAT {0} MORE FOODS TO PASS"),
+    ("pay","YOU PAY {0} COINS"),
+    ("vortex_nowhere","THIS VORTEX LEADS NOWHERE"),
+    ("vortex_swallows","THE VORTEX SWALLOWS YOU"),
+    ("vortex_spits","THE VORTEX SPITS YOU BACK OUT"),
+    ("hurt","{0}, HEALTH {1}"),
+    ("burnt","YOU GET BURNT"),
+    ("creature_hurts","{0} HURTS YOU"),
+    ("tile_hurts","YOU STEP ON {0}"),
+    ("creature_bumps","{0} BUMPS INTO YOU"),
+    ("creature_says","{0}: {1}"),
+    ("need_boat","YOU NEED {0} TO CROSS THE WATER"),
+    ("cannot_swim","YOU CANNOT SWIM"),
+    ("checkpoint","YOU REACH A CHECKPOINT"),
+    ("die","YOU DIE.  GAME OVER"),
+    ("respawn","YOU DIE AND COME BACK TO LIFE"),
+    ("no_respawn","YOU DIE AND HAVE NOWHERE TO COME BACK TO"),
+    ("wont_budge","IT WON'T BUDGE"),
+    ("click","CLICK"),
+    ("slot_paid","THE SLOT HAS BEEN PAID"),
+    ("insert_coin","INSERT A COIN"),
+    ("sign_blank","THE SIGN IS BLANK"),
+    ("one_way","YOU CANNOT GO THROUGH THIS WAY"),
+    ("goal.rainbow","REACH THE RAINBOW"),
+    ("goal.coins","COLLECT ALL COINS"),
+    ("goal.collect","COLLECT {0} x {1}"),
+    ("goal.deliver","BRING {0} TO ROOM {1}"),
+    ("goal.room","REACH ROOM {0}"),
+    ("goal.room_within","REACH ROOM {0} IN {1} S"),
+    ("goal.plates","PUT A CRATE ON EVERY PLATE"),
+    ("goal.time_left","{0} ({1} S LEFT)"),
+    ("hud.foods","FOODS"),
+    ("hud.coins","COINS"),
+    ("hud.health","HEALTH"),
+    ("hud.lives","LIVES"),
+    ("hud.score","SCORE"),
+    ("hud.won","YOU WON THE GAME !!!"),
+    ("hud.game_over","GAME OVER"),
+    ("hud.messages","MESSAGES (PAGE UP/DOWN TO SCROLL, L TO CLOSE)"),
+];
+
+fn english(key:&str)->Option<&'static str> {
+    ENGLISH.iter().find(|&&(k,_)| k == key).map(|&(_,v)| v)
+}
+
+/// Message templates of a language, falling back to English
+#[derive(Clone,Debug,Default)]
+pub struct Catalog {
+    texts:BTreeMap<String,String>
+}
+
+impl Catalog {
+    /// A catalog with only the built-in English messages
+    pub fn english()->Self {
+	Self::default()
+    }
+
+    /// Read a locale file made of `key = template` lines, where
+    /// blank lines and lines starting with `#` are ignored.  Objects
+    /// are named by keys such as `object.K`, K being their code.
+    pub fn parse(text:&str)->Result<Self> {
+	let mut texts = BTreeMap::new();
+	for (i,line) in text.lines().enumerate() {
+	    let line = line.trim();
+	    if line.is_empty() || line.starts_with('#') {
+		continue;
+	    }
+	    match line.split_once('=') {
+		None => bail!("Line {}: expected key = text",i + 1),
+		Some((k,v)) => {
+		    let k = k.trim();
+		    if english(k).is_none() && !k.starts_with("object.") {
+			bail!("Line {}: unknown message {:?}",i + 1,k);
+		    }
+		    texts.insert(k.to_string(),v.trim().to_string());
+		}
+	    }
+	}
+	Ok(Self { texts })
+    }
+
+    pub fn load<P:AsRef<Path>>(path:P)->Result<Self> {
+	Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// The template of a message
+    pub fn get<'a>(&'a self,key:&'a str)->&'a str {
+	match self.texts.get(key) {
+	    Some(u) => u,
+	    None => english(key).unwrap_or(key)
+	}
+    }
+
+    /// The name of an object, `name` being the one given by the world
+    pub fn object(&self,obj:Object,name:&str)->String {
+	match self.texts.get(&format!("object.{}",obj.0)) {
+	    Some(u) => u.clone(),
+	    None => name.to_string()
+	}
+    }
+
+    pub fn render(&self,msg:&Message)->String {
+	let mut u = self.get(msg.key).to_string();
+	for (i,arg) in msg.args.iter().enumerate() {
+	    let value =
+		match arg {
+		    Arg::Text(t) => t.clone(),
+		    Arg::Number(n) => n.to_string(),
+		    Arg::Object(obj,name) => self.object(*obj,name),
+		    Arg::Message(m) => self.render(m)
+		};
+	    u = u.replace(&format!("{{{}}}",i),&value);
+	}
+	u
+    }
+}
+
+impl Display for Message {
+    fn fmt(&self,f:&mut Formatter<'_>)->std::result::Result<(),std::fmt::Error> {
+	write!(f,"{}",Catalog::english().render(self))
+    }
+}
+
+#[test]
+fn test_catalog() {
+    let m = msg!("hurt",msg!("creature_hurts","BAT"),2);
+    assert_eq!(m.to_string(),"BAT HURTS YOU, HEALTH 2");
+    let fr = Catalog::parse("# French\ncreature_hurts = {0} VOUS BLESSE\nhurt = {0}, SANTE {1}\n\
+			     object.K = UNE CLE\n").unwrap();
+    assert_eq!(fr.render(&m),"BAT VOUS BLESSE, SANTE 2");
+    let key = ObjectType::defaults().into_iter().find(|ot| ot.object() == Object::KEY).unwrap();
+    assert_eq!(fr.render(&msg!("take",&key)),"YOU TAKE UNE CLE");
+    assert_eq!(msg!("take",&key).to_string(),"YOU TAKE A KEY");
+    assert!(Catalog::parse("nonsense = X").is_err());
+    assert!(Catalog::parse("hurt").is_err());
+    Catalog::load("../locales/fr.txt").unwrap();
+}
@@ -9,7 +9,11 @@ use serde::{
 };
 
 use crate::{
-    object::Object
+    facing::Facing,
+    mini_rng::MiniRNG,
+    object::Object,
+    room::Room,
+    sounds::Sounds
 };
 
 #[derive(PartialEq,Copy,Clone,Debug,Serialize,Deserialize)]
@@ -59,7 +63,42 @@ pub struct Door {
     pub id:usize,
     pub target:Option<Target>,
     pub key:Option<Object>,
-    pub locked:bool
+    pub locked:bool,
+    #[serde(default)]
+    pub requires:Option<Requirement>
+}
+
+/// What the hero must have achieved to go through a door, besides
+/// having the key
+#[derive(PartialEq,Copy,Clone,Debug,Serialize,Deserialize)]
+pub enum Requirement {
+    /// Have that many coins, paying them if `consume` is set
+    Coins { count:usize,consume:bool },
+    /// Have eaten that many foods
+    Foods { count:usize }
+}
+
+impl Requirement {
+    /// Parse the kind and count of a requirement, as in `COINS 3`,
+    /// `TOLL 3` or `FOODS 2`
+    pub fn parse(kind:&str,count:usize)->Option<Self> {
+	match kind {
+	    "COINS" => Some(Requirement::Coins { count,consume:false }),
+	    "TOLL" => Some(Requirement::Coins { count,consume:true }),
+	    "FOODS" => Some(Requirement::Foods { count }),
+	    _ => None
+	}
+    }
+}
+
+impl Display for Requirement {
+    fn fmt(&self,f:&mut Formatter<'_>)->Result<(),std::fmt::Error> {
+	match self {
+	    Requirement::Coins { count,consume:false } => write!(f,"{} COINS",count),
+	    Requirement::Coins { count,consume:true } => write!(f,"A TOLL OF {} COINS",count),
+	    Requirement::Foods { count } => write!(f,"{} FOODS EATEN",count)
+	}
+    }
 }
 
 #[derive(PartialEq,Copy,Clone,Debug,Serialize,Deserialize)]
@@ -70,6 +109,21 @@ pub enum Corner {
     SW
 }
 
+impl Corner {
+    /// Where a ramp sends the hero entering it going along `f`, the
+    /// corner being its solid half; `None` if it enters from a solid
+    /// side
+    pub fn deflect(self,f:Facing)->Option<Facing> {
+	match (self,f) {
+	    (Corner::NE,Facing::Up) | (Corner::SE,Facing::Down) => Some(Facing::Left),
+	    (Corner::NW,Facing::Up) | (Corner::SW,Facing::Down) => Some(Facing::Right),
+	    (Corner::NE,Facing::Right) | (Corner::NW,Facing::Left) => Some(Facing::Down),
+	    (Corner::SE,Facing::Right) | (Corner::SW,Facing::Left) => Some(Facing::Up),
+	    _ => None
+	}
+    }
+}
+
 #[derive(PartialEq,Copy,Clone,Debug,Serialize,Deserialize)]
 pub enum Tile {
     Empty,
@@ -88,13 +142,63 @@ pub enum Tile {
     Alien,
     MetalRamp(Corner),
     MetalFoot,
-    Sky(Random)
+    Sky(Random),
+    /// A block the hero can push, possibly resting on a pressure plate
+    Crate { on_plate:bool },
+    /// A pressure plate, pressed by crates and by the hero
+    Plate,
+    /// Toggled by walking into it
+    Lever { on:bool },
+    /// Takes a coin, once
+    CoinSlot { paid:bool },
+    /// Shows the text the world gives for its tile
+    Sign,
+    /// Keeps the hero sliding until something stops it
+    Ice,
+    /// Carries the hero along its direction
+    Conveyor(Facing),
+    /// Can only be entered going along its direction
+    OneWay(Facing),
+    /// Terrain of a type declared by the world, known by its code
+    Custom { code:char,phase:Periodic }
 }
 
 impl Tile {
+    /// The floor left behind when a crate is pushed away from this
+    /// tile, and the tile a crate makes when pushed onto it
+    pub fn floor(self)->Option<(Tile,Tile)> {
+	match self {
+	    Tile::Empty | Tile::Crate { on_plate:false } =>
+		Some((Tile::Empty,Tile::Crate { on_plate:false })),
+	    Tile::Plate | Tile::Crate { on_plate:true } =>
+		Some((Tile::Plate,Tile::Crate { on_plate:true })),
+	    _ => None
+	}
+    }
+
+    /// Can the hero enter this tile of moving floor going along `f`?
+    pub fn admits(self,f:Facing)->bool {
+	match self {
+	    Tile::Ice | Tile::Conveyor(_) => true,
+	    Tile::OneWay(g) => g == f,
+	    Tile::MetalRamp(c) => c.deflect(f).is_some(),
+	    _ => false
+	}
+    }
+
+    /// Where the tile moves the hero who arrived on it going along `f`
+    pub fn drift(self,f:Facing)->Option<Facing> {
+	match self {
+	    Tile::Ice => Some(f),
+	    Tile::Conveyor(g) => Some(g),
+	    Tile::MetalRamp(c) => c.deflect(f),
+	    _ => None
+	}
+    }
+
     pub fn next(&mut self) {
 	match self {
-	    Tile::Fire(p) | Tile::Water(p) => p.next(),
+	    Tile::Fire(p) | Tile::Water(p) | Tile::Custom { phase:p,.. } => p.next(),
 	    _ => ()
 	}
     }
@@ -106,7 +210,7 @@ impl Display for Tile {
 	    Tile::Empty => write!(f,"Nothing")?,
 	    Tile::Brick => write!(f,"A brick")?,
 	    Tile::Rainbow => write!(f,"A rainbow")?,
-	    Tile::Object(o) => write!(f,"{}",o.name())?,
+	    Tile::Object(o) => write!(f,"Object {}",o.0)?,
 	    Tile::Vortex => write!(f,"A vortex")?,
 	    Tile::Grass => write!(f,"Grass")?,
 	    Tile::Dirt => write!(f,"Dirt")?,
@@ -120,14 +224,17 @@ impl Display for Tile {
 		    None => (),
 		    Some(k) => {
 			if d.locked {
-			    write!(f,"  It's locked, needs {} to open.",
-				   k.name())?;
+			    write!(f,"  It's locked, needs object {} to open.",
+				   k.0)?;
 			} else {
-			    write!(f,"  It was unlocked with {}.",
-				   k.name())?;
+			    write!(f,"  It was unlocked with object {}.",
+				   k.0)?;
 			}
 		    }
 		}
+		if let Some(req) = d.requires {
+		    write!(f,"  Requires {}.",req)?;
+		}
 		match d.target {
 		    None => write!(f,"  This door goes nowhere!")?,
 		    Some(Target { room,door }) =>
@@ -140,7 +247,84 @@ impl Display for Tile {
 	    Tile::MetalRamp(_) => write!(f,"A metal ramp")?,
 	    Tile::MetalFoot => write!(f,"A metal foot")?,
 	    Tile::Sky(_) => write!(f,"The sky")?,
+	    Tile::Crate { on_plate:false } => write!(f,"A crate")?,
+	    Tile::Crate { on_plate:true } => write!(f,"A crate on a pressure plate")?,
+	    Tile::Plate => write!(f,"A pressure plate")?,
+	    Tile::Lever { on } => write!(f,"A lever, {}",if *on { "on" } else { "off" })?,
+	    Tile::CoinSlot { paid } => write!(f,"A coin slot{}",if *paid { ", paid" } else { "" })?,
+	    Tile::Sign => write!(f,"A sign")?,
+	    Tile::Ice => write!(f,"Ice")?,
+	    Tile::Conveyor(g) => write!(f,"A conveyor belt going {:?}",g)?,
+	    Tile::OneWay(g) => write!(f,"A one-way passage going {:?}",g)?,
+	    Tile::Custom { code,.. } => write!(f,"Terrain {}",code)?,
 	}
 	Ok(())
     }
 }
+
+/// The properties of a kind of terrain, as declared by a world
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct TileType {
+    /// The character standing for the tile in room maps
+    pub code:char,
+    pub name:String,
+    /// Row and column of the first sprite in the tile sheet, further
+    /// animation frames lying below it
+    pub sprite:(usize,usize),
+    /// Number of animation frames
+    #[serde(default = "one")]
+    pub frames:u16,
+    /// Ticks spent on each frame
+    #[serde(default = "one")]
+    pub period:u16,
+    /// Can the hero walk on it?
+    #[serde(default)]
+    pub passable:bool,
+    /// Health lost by stepping on it, when hazards are enabled
+    #[serde(default)]
+    pub damage:usize,
+    /// Played when the hero steps on it
+    #[serde(default)]
+    pub sound:Option<Sounds>
+}
+
+fn one()->u16 {
+    1
+}
+
+impl TileType {
+    pub fn new(code:char,name:&str,sprite:(usize,usize))->Self {
+	Self {
+	    code,
+	    name:name.to_string(),
+	    sprite,
+	    frames:1,
+	    period:1,
+	    passable:false,
+	    damage:0,
+	    sound:None
+	}
+    }
+
+    /// Stands for tiles the world does not declare
+    pub fn unknown(code:char)->Self {
+	Self::new(code,&format!("UNKNOWN TERRAIN ({})",code),(2,3))
+    }
+
+    /// Can the character stand for a declared tile type, without
+    /// clashing with the built-in tiles and objects?
+    pub fn is_free_code(code:char)->bool {
+	code.is_ascii_graphic() && !code.is_ascii_alphanumeric() &&
+	    Room::char_tile(code,&mut MiniRNG::new(1)).is_none()
+    }
+
+    /// A fresh tile of this type
+    pub fn tile(&self)->Tile {
+	Tile::Custom { code:self.code,phase:Periodic::new(self.frames.max(1),self.period.max(1)) }
+    }
+
+    /// Row and column of the sprite to draw for a tile of this type
+    pub fn sprite_at(&self,phase:Periodic)->(usize,usize) {
+	(self.sprite.0 + phase.i as usize,self.sprite.1)
+    }
+}
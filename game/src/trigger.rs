@@ -0,0 +1,36 @@
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    tiles::Tile,
+    world::TileAddress
+};
+
+/// A tile toggled by a trigger: a door, which gets locked or
+/// unlocked, or a wall, which disappears or comes back
+#[derive(Copy,Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct SwitchTarget {
+    pub at:TileAddress,
+    /// What the tile is when the wall is there
+    pub closed:Tile
+}
+
+/// A lever, pressure plate or coin slot and the tiles it toggles, in
+/// any room
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Trigger {
+    pub at:TileAddress,
+    pub targets:Vec<SwitchTarget>,
+    /// Whether a pressure plate was last seen pressed
+    #[serde(default)]
+    pub active:bool
+}
+
+impl Trigger {
+    /// Can a tile act as a trigger?
+    pub fn can_trigger(tile:Tile)->bool {
+	matches!(tile,Tile::Lever { .. } | Tile::CoinSlot { .. } | Tile::Plate | Tile::Crate { on_plate:true })
+    }
+}
@@ -0,0 +1,23 @@
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::world::TileAddress;
+
+/// Where a vortex sends the hero
+#[derive(Copy,Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Destination {
+    /// A given tile, usually another vortex
+    Tile(TileAddress),
+    /// Any other vortex of the world, picked at random
+    Random
+}
+
+/// The destination of the vortex at a given tile.  Kept in the world
+/// rather than in the tile, which stays a plain `Tile::Vortex`.
+#[derive(Copy,Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct VortexLink {
+    pub at:TileAddress,
+    pub to:Destination
+}
@@ -25,9 +25,32 @@ use serde::{
 };
 
 use crate::{
+    creature::{
+	Behaviour,
+	Creature
+    },
+    dialogue::{
+	Dialogue,
+	Sign
+    },
+    goal::Goal,
+    hazards::Hazards,
     room::Room,
-    object::Object,
+    inventory::Inventory,
+    object::{
+	Object,
+	ObjectType
+    },
     tiles::*,
+    script::Script,
+    trigger::{
+	SwitchTarget,
+	Trigger
+    },
+    vortex::{
+	Destination,
+	VortexLink
+    },
     ptr::*
 };
 
@@ -35,6 +58,41 @@ use crate::{
 pub struct World {
     pub rooms:BTreeMap<usize,Ptr<Room>>,
     pub start:Option<TileAddress>,
+    /// Number of objects the hero can carry
+    #[serde(default = "default_inventory_capacity")]
+    pub inventory_capacity:usize,
+    /// What the hero must do to win; reaching a rainbow if empty
+    #[serde(default)]
+    pub goals:Vec<Goal>,
+    #[serde(default)]
+    pub vortices:Vec<VortexLink>,
+    #[serde(default)]
+    pub hazards:Hazards,
+    #[serde(default)]
+    pub triggers:Vec<Trigger>,
+    #[serde(default)]
+    pub scripts:Vec<Script>,
+    #[serde(default)]
+    pub creatures:Vec<Creature>,
+    #[serde(default)]
+    pub signs:Vec<Sign>,
+    /// The kinds of objects found in the world
+    #[serde(default = "ObjectType::defaults")]
+    pub objects:Vec<ObjectType>,
+    /// The kinds of terrain the world adds to the built-in tiles
+    #[serde(default)]
+    pub tile_types:Vec<TileType>,
+}
+
+fn default_inventory_capacity()->usize {
+    Inventory::DEFAULT_CAPACITY
+}
+
+/// What follows the first word of a stanza line numbered `ln`
+fn rest_of(line:&str,ln:usize)->Result<&str> {
+    line.split_once(' ')
+	.map(|(_,rest)| rest)
+	.ok_or_else(|| anyhow!("Missing argument at line {}",ln))
 }
 
 #[derive(Copy,Clone,Debug,PartialEq,Serialize,Deserialize)]
@@ -47,7 +105,26 @@ pub struct TileAddress {
 impl World {
     pub fn clear(&mut self) {
 	self.rooms.clear();
-	self.start = None
+	self.start = None;
+	self.inventory_capacity = Inventory::DEFAULT_CAPACITY;
+	self.goals.clear();
+	self.vortices.clear();
+	self.hazards = Hazards::default();
+	self.triggers.clear();
+	self.scripts.clear();
+	self.creatures.clear();
+	self.signs.clear();
+	self.objects = ObjectType::defaults();
+	self.tile_types.clear()
+    }
+
+    /// A copy of the world that does not share its rooms with this one
+    pub fn deep_copy(&self)->Self {
+	let mut world = self.clone();
+	world.rooms = self.rooms.iter()
+	    .map(|(&id,room_ptr)| (id,Ptr::make(room_ptr.yank().clone())))
+	    .collect();
+	world
     }
 
     pub fn save<P:AsRef<Path>>(&self,path:P)->Result<()> {
@@ -61,81 +138,270 @@ impl World {
 	self.rooms.keys().copied().collect()
     }
 
+    /// Load a world, either in RON or in the older text format.  On
+    /// failure the current world is left untouched.
     pub fn load<P:AsRef<Path>>(&mut self,path:P)->Result<()> {
 	let fd = File::open(path)?;
 	let mut buf = BufReader::new(fd);
-	if let Ok(this) = ron::de::from_reader::<_,World>(&mut buf) {
-	    self.rooms = this.rooms;
-	    self.start = this.start;
-	    Ok(())
-	} else {
-	    buf.rewind()?;
-	    let line_number = RefCell::new(0);
-	    let mut f = ||->Result<String> {
-		let mut u = String::new();
-		let _ = buf.read_line(&mut u)?;
-		let mut ln = line_number.borrow_mut();
-		*ln += 1;
-		Ok(u.trim_end_matches('\n').to_string())
+	let mut world =
+	    if let Ok(this) = ron::de::from_reader::<_,World>(&mut buf) {
+		this
+	    } else {
+		buf.rewind()?;
+		Self::load_text(&mut buf)?
 	    };
-	    let g = |u:&str|->Result<usize> {
-		u.parse::<usize>()
-		    .map_err(|_| anyhow!("Bad integer {u} at line {}",
-					 *(line_number.borrow())))
+	world.validate()?;
+	*self = world;
+	Ok(())
+    }
+
+    fn load_text<R:BufRead>(buf:&mut R)->Result<Self> {
+	let mut world = World::new();
+	let line_number = RefCell::new(0);
+	let mut f = ||->Result<String> {
+	    let mut u = String::new();
+	    let n = buf.read_line(&mut u)?;
+	    let mut ln = line_number.borrow_mut();
+	    *ln += 1;
+	    if n == 0 {
+		bail!("Unexpected end of file at line {}",*ln);
+	    }
+	    Ok(u.trim_end_matches('\n').trim_end_matches('\r').to_string())
+	};
+	let g = |u:&str|->Result<usize> {
+	    u.parse::<usize>()
+		.map_err(|_| anyhow!("Bad integer {u} at line {}",
+				     *(line_number.borrow())))
+	};
+	loop {
+	    let line = f()?;
+	    if line.trim_start().starts_with("//") || line.trim_start().is_empty() {
+		continue;
+	    }
+	    let ln = *line_number.borrow();
+	    let words : Vec<&str> = line.split(' ').collect();
+	    match words[..] {
+		["END"] => break,
+		["CONN",room1,door1,room2,door2] =>
+		    world.try_connect(g(room1)?,g(door1)?,g(room2)?,g(door2)?)
+		    .map_err(|e| anyhow!("{} at line {}",e,ln))?,
+		["LOCK",room,door,object] => {
+		    let cs : Vec<char> = object.chars().collect();
+		    let obj = 
+			if cs.len() == 1 {
+			    Object::from_char(cs[0])?
+			} else {
+			    bail!("Invalid object string {:?}",object);
+			};
+		    let (room,door) = (g(room)?,g(door)?);
+		    if world.get_door(room,door).is_none() {
+			bail!("Cannot lock missing door {} of room {} at line {}",door,room,ln);
+		    }
+		    world.lock_door_with(room,door,obj);
+		},
+		["CAPACITY",n] => world.inventory_capacity = g(n)?,
+		["GOAL",ref goal @ ..] =>
+		    world.goals.push(Goal::parse(goal)
+				     .map_err(|e| anyhow!("{} at line {}",e,ln))?),
+		["REQUIRE",room,door,kind,count] => {
+		    let (room,door) = (g(room)?,g(door)?);
+		    let req = Requirement::parse(kind,g(count)?)
+			.ok_or_else(|| anyhow!("Invalid requirement {:?} at line {}",kind,ln))?;
+		    if world.get_door(room,door).is_none() {
+			bail!("Cannot set requirement of missing door {} of room {} at line {}",door,room,ln);
+		    }
+		    world.require(room,door,req);
+		},
+		["VORTEX",room,iy,ix,ref to @ ..] => {
+		    let at = TileAddress { room_id:g(room)?,iy:g(iy)?,ix:g(ix)? };
+		    let to =
+			match to {
+			    ["RANDOM"] => Destination::Random,
+			    [room,iy,ix] => Destination::Tile(TileAddress { room_id:g(room)?,iy:g(iy)?,ix:g(ix)? }),
+			    _ => bail!("Invalid vortex destination at line {}",ln)
+			};
+		    world.set_vortex(at,Some(to))
+			.map_err(|e| anyhow!("{} at line {}",e,ln))?;
+		},
+		["HAZARDS",health,lives,damage] => {
+		    world.hazards.enabled = true;
+		    world.hazards.health = g(health)?;
+		    world.hazards.lives = if lives == "INF" { None } else { Some(g(lives)?) };
+		    world.hazards.fire_damage = g(damage)?;
+		},
+		["FIREPHASES",ref phases @ ..] => {
+		    for p in phases {
+			world.hazards.fire_phases.push(g(p)? as u16);
+		    }
+		},
+		["BOAT",object] => {
+		    let cs : Vec<char> = object.chars().collect();
+		    if cs.len() != 1 {
+			bail!("Invalid object string {:?}",object);
+		    }
+		    world.hazards.boat = Some(Object::from_char(cs[0])?);
+		},
+		["CHECKPOINT",room,iy,ix] =>
+		    world.hazards.checkpoints.push(TileAddress { room_id:g(room)?,iy:g(iy)?,ix:g(ix)? }),
+		["TRIGGER",room,iy,ix,room2,iy2,ix2] => {
+		    let at = TileAddress { room_id:g(room)?,iy:g(iy)?,ix:g(ix)? };
+		    let target = TileAddress { room_id:g(room2)?,iy:g(iy2)?,ix:g(ix2)? };
+		    world.wire(at,target)
+			.map_err(|e| anyhow!("{} at line {}",e,ln))?;
+		},
+		["SCRIPT",..] => {
+		    let script = ron::de::from_str(rest_of(&line,ln)?)
+			.map_err(|e| anyhow!("Invalid script at line {}: {}",ln,e))?;
+		    world.scripts.push(script);
+		},
+		["CREATURE",..] => {
+		    let creature = ron::de::from_str(rest_of(&line,ln)?)
+			.map_err(|e| anyhow!("Invalid creature at line {}: {}",ln,e))?;
+		    world.creatures.push(creature);
+		},
+		["OBJECT",..] => {
+		    let ot : ObjectType = ron::de::from_str(rest_of(&line,ln)?)
+			.map_err(|e| anyhow!("Invalid object at line {}: {}",ln,e))?;
+		    Object::from_char(ot.code)
+			.map_err(|e| anyhow!("{} at line {}",e,ln))?;
+		    if !ObjectType::is_free_code(ot.code) {
+			bail!("Object code {:?} is already taken at line {}",ot.code,ln);
+		    }
+		    world.declare(ot);
+		},
+		["TILE",..] => {
+		    let tt : TileType = ron::de::from_str(rest_of(&line,ln)?)
+			.map_err(|e| anyhow!("Invalid tile type at line {}: {}",ln,e))?;
+		    if !TileType::is_free_code(tt.code) {
+			bail!("Tile code {:?} is already taken at line {}",tt.code,ln);
+		    }
+		    world.declare_tile(tt);
+		},
+		["SIGN",room,iy,ix] => {
+		    let at = TileAddress { room_id:g(room)?,iy:g(iy)?,ix:g(ix)? };
+		    let mut text = String::new();
+		    loop {
+			let line = f()?;
+			if line.starts_with(' ') {
+			    let (_,rest) = line.split_once(' ').unwrap();
+			    text.push_str(rest);
+			    text.push('\n');
+			} else if line == "ENDSIGN" {
+			    break;
+			} else {
+			    bail!("Invalid sign line {}",*line_number.borrow());
+			}
+		    }
+		    let dialogue = Dialogue::parse(&text)
+			.map_err(|e| anyhow!("Sign at line {}: {}",ln,e))?;
+		    world.set_sign(at,dialogue)
+			.map_err(|e| anyhow!("{} at line {}",e,ln))?;
+		},
+		["START",room] => {
+		    let _ = g(room)?;
+		},
+		["ROOM",id] => {
+		    let id = g(id)?;
+		    if world.rooms.contains_key(&id) {
+			bail!("Room {} defined again at line {}",id,ln);
+		    }
+		    let name = f()?;
+		    let mut descr : Vec<String> = Vec::new();
+		    loop {
+			let line = f()?;
+			if line.starts_with(' ') {
+			    let (_,rest) = line.split_once(' ').unwrap();
+			    descr.push(rest.to_string());
+			} else if line == "ENDROOM" {
+			    break;
+			} else {
+			    bail!("Invalid room line {}",*line_number.borrow());
+			}
+		    }
+		    let descr_ref : Vec<&str> = descr.iter().map(|x| x.as_str())
+			.collect();
+		    let (room,start) = Room::parse_with(id,&name,&descr_ref[..],Some(Tile::Brick),
+							&world.tile_types)
+			.map_err(|e| anyhow!("Room {} at line {}: {}",id,ln,e))?;
+		    world.insert_room(room);
+		    world.start = world.start.or(start);
+		},
+		_ => bail!("Invalid stanza {:?} at line {}",line,ln)
 	    };
-	    loop {
-		let line = f()?;
-		if line.trim_start().starts_with("//") || line.trim_start().is_empty() {
-		    continue;
+	}
+	Ok(world)
+    }
+
+    /// Check the consistency of a world coming from a file or over the
+    /// link
+    pub fn validate(&mut self)->Result<()> {
+	for (&id,room_ptr) in self.rooms.iter() {
+	    let mut rm = room_ptr.yank_mut();
+	    if rm.id != id {
+		bail!("Room {} is stored under number {}",rm.id,id);
+	    }
+	    rm.validate().map_err(|e| anyhow!("Room {}: {}",id,e))?;
+	}
+	for room_ptr in self.rooms.values() {
+	    let rm = room_ptr.yank();
+	    for iy in 0..rm.rows {
+		for ix in 0..rm.cols {
+		    match rm.map[[iy,ix]] {
+			Tile::Object(o) if self.object_type(o).is_none() =>
+			    bail!("Room {}: undeclared object {:?} at ({},{})",rm.id,o.0,iy,ix),
+			Tile::Custom { code,.. } if self.tile_type(code).is_none() =>
+			    bail!("Room {}: undeclared tile {:?} at ({},{})",rm.id,code,iy,ix),
+			_ => ()
+		    }
 		}
-		let words : Vec<&str> = line.split(' ').collect();
-		match words[..] {
-		    ["END"] => break,
-		    ["CONN",room1,door1,room2,door2] =>
-			self.connect(g(room1)?,g(door1)?,g(room2)?,g(door2)?),
-		    ["LOCK",room,door,object] => {
-			let cs : Vec<char> = object.chars().collect();
-			let obj = 
-			    if cs.len() == 1 {
-				Object::from_char(cs[0])?
-			    } else {
-				bail!("Invalid object string {:?}",object);
-			    };
-			self.lock_door_with(g(room)?,g(door)?,obj);
-		    },
-		    ["START",room] => {
-			let _ = g(room)?;
-		    },
-		    ["ROOM",id] => {
-			let name = f()?;
-			let mut descr : Vec<String> = Vec::new();
-			loop {
-			    let line = f()?;
-			    if line.starts_with(' ') {
-				let (_,rest) = line.split_once(' ').unwrap();
-				descr.push(rest.to_string());
-			    } else if line == "ENDROOM" {
-				break;
-			    } else {
-				bail!("Invalid room line");
-			    }
-			}
-			let descr_ref : Vec<&str> = descr.iter().map(|x| x.as_str())
-			    .collect();
-			let start = self.add_room(g(id)?,&name,&descr_ref[..]);
-			self.start = self.start.or(start);
-		    },
-		    _ => bail!("Invalid stanza {:?}",line)
+	    }
+	}
+	for c in self.creatures.iter() {
+	    let (rows,cols) =
+		match self.rooms.get(&c.room) {
+		    None => bail!("Creature {:?} is in missing room {}",c.name,c.room),
+		    Some(room_ptr) => room_ptr.yank().dims()
 		};
+	    for (iy,ix) in c.points() {
+		if iy >= rows || ix >= cols {
+		    bail!("Creature {:?}: ({},{}) is outside room {}",c.name,iy,ix,c.room);
+		}
+	    }
+	}
+	if !(1..=Inventory::MAX_CAPACITY).contains(&self.inventory_capacity) {
+	    bail!("The inventory capacity must be between 1 and {}",Inventory::MAX_CAPACITY);
+	}
+	if self.hazards.enabled && self.hazards.health == 0 {
+	    bail!("The hero must start with some health");
+	}
+	if let Some(ta) = self.start {
+	    let inside = self.rooms.get(&ta.room_id)
+		.map(|room_ptr| {
+		    let (rows,cols) = room_ptr.yank().dims();
+		    ta.iy < rows && ta.ix < cols
+		})
+		.unwrap_or(false);
+	    if !inside {
+		bail!("Start position {:?} is outside the world",ta);
 	    }
-	    Ok(())
 	}
+	Ok(())
     }
 
     pub fn new()->Self {
 	World {
 	    rooms:BTreeMap::new(),
 	    start:None,
+	    inventory_capacity:Inventory::DEFAULT_CAPACITY,
+	    goals:Vec::new(),
+	    vortices:Vec::new(),
+	    hazards:Hazards::default(),
+	    triggers:Vec::new(),
+	    scripts:Vec::new(),
+	    creatures:Vec::new(),
+	    signs:Vec::new(),
+	    objects:ObjectType::defaults(),
+	    tile_types:Vec::new()
 	}
     }
 
@@ -147,14 +413,218 @@ impl World {
 	self.rooms.insert(room.id,Ptr::make(room));
     }
 
-    pub fn add_room(&mut self,id:usize,name:&str,descr:&[&str])->Option<TileAddress> {
-	let (room,start) = Room::new(id,name,descr);
+    pub fn add_room(&mut self,id:usize,name:&str,descr:&[&str])->Result<Option<TileAddress>> {
+	let (room,start) = Room::parse_with(id,name,descr,None,&self.tile_types)?;
 	self.insert_room(room);
-	start
+	Ok(start)
+    }
+
+    /// Doors of other rooms whose target is in room `id`, as
+    /// (room,door) pairs
+    pub fn incoming_doors(&self,id:usize)->Vec<(usize,usize)> {
+	let mut incoming = Vec::new();
+	for (&room_id,room_ptr) in self.rooms.iter() {
+	    if room_id == id {
+		continue;
+	    }
+	    let rm = room_ptr.yank();
+	    for (&door,&(iy,ix)) in rm.doors.iter() {
+		if let Tile::Door(Door { target:Some(Target { room, .. }), .. }) = rm.map[[iy,ix]] {
+		    if room == id {
+			incoming.push((room_id,door));
+		    }
+		}
+	    }
+	}
+	incoming
     }
 
-    pub fn delete_room(&mut self,id:usize) {
+    /// Vortices of other rooms leading into room `id`
+    pub fn incoming_vortices(&self,id:usize)->Vec<TileAddress> {
+	self.vortices.iter()
+	    .filter(|v| v.at.room_id != id && matches!(v.to,Destination::Tile(ta) if ta.room_id == id))
+	    .map(|v| v.at)
+	    .collect()
+    }
+
+    /// Delete room `id`.  Returns the doors of other rooms that were
+    /// leading into it; their targets are cleared if `clear_incoming`
+    /// is set.  The start position is removed if it was in the room,
+    /// and so are the goals that need it and the vortex links leading
+    /// into it.
+    pub fn delete_room(&mut self,id:usize,clear_incoming:bool)->Vec<(usize,usize)> {
+	let incoming = self.incoming_doors(id);
+	if clear_incoming {
+	    for &(room,door) in incoming.iter() {
+		if let Some(mut d) = self.get_door(room,door) {
+		    d.target = None;
+		    self.put_door(room,d);
+		}
+	    }
+	}
+	if self.start.map(|ta| ta.room_id) == Some(id) {
+	    self.start = None;
+	}
+	self.vortices.retain(|v| v.at.room_id != id &&
+			     !matches!(v.to,Destination::Tile(ta) if ta.room_id == id));
+	self.hazards.checkpoints.retain(|c| c.room_id != id);
+	self.triggers.retain(|t| t.at.room_id != id);
+	self.scripts.retain(|s| !s.mentions(id));
+	self.creatures.retain(|c| c.room != id);
+	self.signs.retain(|s| s.at.room_id != id);
+	for t in self.triggers.iter_mut() {
+	    t.targets.retain(|st| st.at.room_id != id);
+	}
+	self.goals.retain(|goal| match goal {
+	    Goal::Deliver { to, .. } => to.room_id != id,
+	    Goal::ReachRoom { room, .. } => *room != id,
+	    _ => true
+	});
 	self.rooms.remove(&id);
+	incoming
+    }
+
+    /// Crop room `id` to the `ny` × `nx` tiles starting at
+    /// `(iy0,ix0)`, moving everything that refers to its tiles along.
+    /// What falls outside is dropped.
+    pub fn crop_room(&mut self,id:usize,iy0:usize,ny:usize,ix0:usize,nx:usize)->Result<()> {
+	{
+	    let room_ptr = self.rooms.get(&id).ok_or_else(|| anyhow!("No room {}",id))?;
+	    let mut rm = room_ptr.yank_mut();
+	    if ny < 1 || nx < 1 || iy0 + ny > rm.rows || ix0 + nx > rm.cols {
+		bail!("Cannot crop room {} to {} × {} at ({},{})",id,ny,nx,iy0,ix0);
+	    }
+	    rm.crop(iy0,ny,ix0,nx);
+	}
+	let g = |(iy,ix):(usize,usize)| {
+	    if iy0 <= iy && iy < iy0 + ny && ix0 <= ix && ix < ix0 + nx {
+		Some((iy - iy0,ix - ix0))
+	    } else {
+		None
+	    }
+	};
+	let f = |ta:TileAddress| {
+	    if ta.room_id != id {
+		Some(ta)
+	    } else {
+		g((ta.iy,ta.ix)).map(|(iy,ix)| TileAddress { room_id:id,iy,ix })
+	    }
+	};
+	let keep = |ta:&mut TileAddress| {
+	    match f(*ta) {
+		Some(new) => { *ta = new; true },
+		None => false
+	    }
+	};
+	self.start = self.start.and_then(f);
+	self.vortices.retain_mut(|v| keep(&mut v.at) && match &mut v.to {
+	    Destination::Tile(ta) => keep(ta),
+	    _ => true
+	});
+	self.hazards.checkpoints.retain_mut(keep);
+	self.triggers.retain_mut(|t| keep(&mut t.at));
+	for t in self.triggers.iter_mut() {
+	    t.targets.retain_mut(|st| keep(&mut st.at));
+	}
+	self.scripts.retain_mut(|s| s.relocate(f));
+	self.signs.retain_mut(|s| keep(&mut s.at));
+	self.goals.retain_mut(|goal| match goal {
+	    Goal::Deliver { to, .. } => keep(to),
+	    _ => true
+	});
+	self.creatures.retain_mut(|c| {
+	    if c.room != id {
+		return true;
+	    }
+	    let Some(home) = g(c.home) else { return false };
+	    c.home = home;
+	    if let Behaviour::Patrol(path) = &mut c.behaviour {
+		*path = path.iter().filter_map(|&p| g(p)).collect();
+	    }
+	    c.reset();
+	    true
+	});
+	Ok(())
+    }
+
+    /// Insert a copy of room `id` with unconnected doors under the next
+    /// free id, which is returned
+    pub fn duplicate_room(&mut self,id:usize)->Option<usize> {
+	let new_id = self.last_id()? + 1;
+	let room = self.rooms.get(&id)?.yank().duplicate(new_id);
+	self.insert_room(room);
+	Some(new_id)
+    }
+
+    /// Change room ids according to `remap`, rewriting door targets
+    /// and the start position.  Rooms not in `remap` keep their ids.
+    pub fn renumber(&mut self,remap:&BTreeMap<usize,usize>)->Result<()> {
+	let f = |id:usize| remap.get(&id).copied().unwrap_or(id);
+	let mut new_ids = BTreeMap::new();
+	for &id in self.rooms.keys() {
+	    if let Some(old) = new_ids.insert(f(id),id) {
+		bail!("Rooms {} and {} would both be numbered {}",old,id,f(id));
+	    }
+	}
+	let rooms = std::mem::take(&mut self.rooms);
+	for (id,room_ptr) in rooms {
+	    {
+		let mut rm = room_ptr.yank_mut();
+		rm.id = f(id);
+		rm.retarget(f);
+	    }
+	    self.rooms.insert(f(id),room_ptr);
+	}
+	if let Some(ta) = self.start.as_mut() {
+	    ta.room_id = f(ta.room_id);
+	}
+	for v in self.vortices.iter_mut() {
+	    v.at.room_id = f(v.at.room_id);
+	    if let Destination::Tile(ta) = &mut v.to {
+		ta.room_id = f(ta.room_id);
+	    }
+	}
+	for s in self.scripts.iter_mut() {
+	    s.renumber(f);
+	}
+	for c in self.creatures.iter_mut() {
+	    c.room = f(c.room);
+	}
+	for s in self.signs.iter_mut() {
+	    s.at.room_id = f(s.at.room_id);
+	}
+	for t in self.triggers.iter_mut() {
+	    t.at.room_id = f(t.at.room_id);
+	    for st in t.targets.iter_mut() {
+		st.at.room_id = f(st.at.room_id);
+	    }
+	}
+	for c in self.hazards.checkpoints.iter_mut() {
+	    c.room_id = f(c.room_id);
+	}
+	for goal in self.goals.iter_mut() {
+	    match goal {
+		Goal::Deliver { to, .. } => to.room_id = f(to.room_id),
+		Goal::ReachRoom { room, .. } => *room = f(*room),
+		_ => ()
+	    }
+	}
+	Ok(())
+    }
+
+    /// Renumber rooms consecutively starting from `first`, keeping their
+    /// order.  Returns the applied mapping.
+    pub fn compact(&mut self,first:usize)->Result<BTreeMap<usize,usize>> {
+	let remap : BTreeMap<usize,usize> = self.rooms.keys()
+	    .enumerate()
+	    .map(|(i,&id)| (id,first + i))
+	    .collect();
+	self.renumber(&remap)?;
+	Ok(remap)
+    }
+
+    pub fn room(&self,id:usize)->Option<Ptr<Room>> {
+	self.rooms.get(&id).map(Ptr::refer)
     }
 
     pub fn get_room(&self,id:usize)->Ptr<Room> {
@@ -168,6 +638,28 @@ impl World {
 	door.key = Some(obj);
     }
 
+    pub fn require(&mut self,room:usize,door:usize,req:Requirement) {
+	let mut room = self.rooms.get(&room).unwrap().yank_mut();
+	room.find_door(door).requires = Some(req);
+    }
+
+    /// Connect two unconnected doors
+    pub fn try_connect(&mut self,room1:usize,door1:usize,room2:usize,door2:usize)->Result<()> {
+	for (room,door) in [(room1,door1),(room2,door2)] {
+	    match self.get_door(room,door) {
+		None => bail!("No door {} in room {}",door,room),
+		Some(Door { target:Some(t), .. }) =>
+		    bail!("Door {} of room {} already leads to {:?}",door,room,t),
+		_ => ()
+	    }
+	}
+	if (room1,door1) == (room2,door2) {
+	    bail!("Door {} of room {} cannot lead to itself",door1,room1);
+	}
+	self.connect(room1,door1,room2,door2);
+	Ok(())
+    }
+
     pub fn connect(&mut self,room1:usize,door1:usize,room2:usize,door2:usize) {
 	{
 	    let mut r1 = self.rooms.get(&room1).unwrap().yank_mut();
@@ -187,14 +679,400 @@ impl World {
 	}
     }
 
+    pub fn door_list(&self,room:usize)->Vec<usize> {
+	self.rooms.get(&room)
+	    .map(|room_ptr| room_ptr.yank().doors.keys().copied().collect())
+	    .unwrap_or_default()
+    }
+
+    pub fn get_door(&self,room:usize,door:usize)->Option<Door> {
+	let room_ptr = self.rooms.get(&room)?;
+	let rm = room_ptr.yank();
+	let (iy,ix) = rm.locate_door(door)?;
+	match rm.map[[iy,ix]] {
+	    Tile::Door(d) => Some(d),
+	    _ => None
+	}
+    }
+
+    /// Replace the properties of door `door` of room `room` with `new`,
+    /// keeping the connection symmetric: the previous target (if any)
+    /// is released and the new target is made to point back to us.
+    pub fn update_door(&mut self,room:usize,door:usize,new:Door)->Result<()> {
+	let old = self.get_door(room,door)
+	    .ok_or_else(|| anyhow!("No door {} in room {}",door,room))?;
+	if new.id != door && self.get_door(room,new.id).is_some() {
+	    bail!("Room {} already has a door {}",room,new.id);
+	}
+	if let Some(Target { room:room2,door:door2 }) = new.target {
+	    if room2 == room && (door2 == door || door2 == new.id) {
+		bail!("Door {} cannot lead to itself",new.id);
+	    }
+	    let d2 = self.get_door(room2,door2)
+		.ok_or_else(|| anyhow!("No door {} in room {}",door2,room2))?;
+	    match d2.target {
+		None => (),
+		Some(Target { room:r,door:d }) if r == room && d == door => (),
+		Some(t) => bail!("Door {} of room {} already goes to door {} of room {}",
+				 door2,room2,t.door,t.room)
+	    }
+	}
+
+	if old.target.is_some() && old.target != new.target {
+	    self.disconnect(room,door)?;
+	}
+
+	{
+	    let mut rm = self.rooms.get(&room).unwrap().yank_mut();
+	    let (iy,ix) = rm.locate_door(door).unwrap();
+	    let _ = rm.modify(iy,ix,Tile::Door(new));
+	}
+
+	if let Some(Target { room:room2,door:door2 }) = new.target {
+	    if let Some(mut d2) = self.get_door(room2,door2) {
+		d2.target = Some(Target { room,door:new.id });
+		self.put_door(room2,d2);
+	    }
+	}
+	Ok(())
+    }
+
+    fn put_door(&mut self,room:usize,door:Door) {
+	let mut rm = self.rooms.get(&room).unwrap().yank_mut();
+	*rm.find_door(door.id) = door;
+    }
+
+    /// Disconnect door `door` of room `room`, also clearing the target of
+    /// the door at the other end if it points back to us.
+    /// Returns the previous target.
+    pub fn disconnect(&mut self,room:usize,door:usize)->Result<Option<Target>> {
+	let mut d1 = self.get_door(room,door)
+	    .ok_or_else(|| anyhow!("No door {} in room {}",door,room))?;
+	let target = d1.target.take();
+	if let Some(Target { room:room2,door:door2 }) = target {
+	    if let Some(mut d2) = self.get_door(room2,door2) {
+		if d2.target == Some(Target { room,door }) {
+		    d2.target = None;
+		    self.put_door(room2,d2);
+		}
+	    }
+	}
+	self.put_door(room,d1);
+	Ok(target)
+    }
+
+    /// Connect two doors, disconnecting them first from whatever they
+    /// were connected to.
+    pub fn reconnect(&mut self,room1:usize,door1:usize,room2:usize,door2:usize)->Result<()> {
+	if self.get_door(room1,door1).is_none() {
+	    bail!("No door {} in room {}",door1,room1);
+	}
+	if self.get_door(room2,door2).is_none() {
+	    bail!("No door {} in room {}",door2,room2);
+	}
+	if (room1,door1) == (room2,door2) {
+	    bail!("Door {} of room {} cannot lead to itself",door1,room1);
+	}
+	self.disconnect(room1,door1)?;
+	self.disconnect(room2,door2)?;
+	self.connect(room1,door1,room2,door2);
+	Ok(())
+    }
+
     pub fn get_tile(&self,ta:&TileAddress)->Option<Tile> {
 	self.rooms.get(&ta.room_id)
-	    .map(|room_ptr| {
+	    .and_then(|room_ptr| {
 		let room = room_ptr.yank();
-		room.map[[ta.iy,ta.ix]]
+		if ta.iy < room.rows && ta.ix < room.cols {
+		    Some(room.map[[ta.iy,ta.ix]])
+		} else {
+		    None
+		}
 	    })
     }
 
+    /// The goals of the world, or the default ones if it has none
+    pub fn active_goals(&self)->&[Goal] {
+	if self.goals.is_empty() {
+	    Goal::DEFAULT
+	} else {
+	    &self.goals
+	}
+    }
+
+    /// Where the vortex at `at` leads, if it has been configured
+    pub fn vortex_destination(&self,at:&TileAddress)->Option<Destination> {
+	self.vortices.iter().find(|v| v.at == *at).map(|v| v.to)
+    }
+
+    /// Set or clear the destination of the vortex at `at`
+    pub fn set_vortex(&mut self,at:TileAddress,to:Option<Destination>)->Result<()> {
+	if self.get_tile(&at) != Some(Tile::Vortex) {
+	    bail!("No vortex in room {} at ({},{})",at.room_id,at.iy,at.ix);
+	}
+	if let Some(Destination::Tile(ta)) = to {
+	    if self.get_tile(&ta).is_none() {
+		bail!("Vortex destination ({},{}) in room {} does not exist",ta.iy,ta.ix,ta.room_id);
+	    }
+	    if ta == at {
+		bail!("A vortex cannot lead to itself");
+	    }
+	}
+	self.vortices.retain(|v| v.at != at);
+	if let Some(to) = to {
+	    self.vortices.push(VortexLink { at,to });
+	}
+	Ok(())
+    }
+
+    /// Make two vortices lead to each other
+    pub fn link_vortices(&mut self,a:TileAddress,b:TileAddress)->Result<()> {
+	if self.get_tile(&b) != Some(Tile::Vortex) {
+	    bail!("No vortex in room {} at ({},{})",b.room_id,b.iy,b.ix);
+	}
+	self.set_vortex(a,Some(Destination::Tile(b)))?;
+	self.set_vortex(b,Some(Destination::Tile(a)))
+    }
+
+    /// All the vortex tiles of the world
+    pub fn vortex_list(&self)->Vec<TileAddress> {
+	let mut list = Vec::new();
+	for (&room_id,room_ptr) in self.rooms.iter() {
+	    let rm = room_ptr.yank();
+	    for iy in 0..rm.rows {
+		for ix in 0..rm.cols {
+		    if rm.map[[iy,ix]] == Tile::Vortex {
+			list.push(TileAddress { room_id,iy,ix });
+		    }
+		}
+	    }
+	}
+	list
+    }
+
+    /// Vortex links that are no longer valid, because their vortex or
+    /// their destination has gone
+    pub fn dangling_vortices(&self)->Vec<VortexLink> {
+	self.vortices.iter()
+	    .filter(|v| {
+		self.get_tile(&v.at) != Some(Tile::Vortex) ||
+		    match v.to {
+			Destination::Tile(ta) => self.get_tile(&ta).is_none(),
+			Destination::Random => false
+		    }
+	    })
+	    .copied()
+	    .collect()
+    }
+
+    /// Connect a trigger to a target tile, or disconnect it if it
+    /// already was.  Returns true if they are now connected.
+    pub fn wire(&mut self,at:TileAddress,target:TileAddress)->Result<bool> {
+	match self.get_tile(&at) {
+	    Some(t) if Trigger::can_trigger(t) => (),
+	    _ => bail!("No lever, plate or coin slot in room {} at ({},{})",at.room_id,at.iy,at.ix)
+	}
+	let closed =
+	    match self.get_tile(&target) {
+		None => bail!("No tile in room {} at ({},{})",target.room_id,target.iy,target.ix),
+		Some(Tile::Empty) => Tile::Brick,
+		Some(t) => t
+	    };
+	if at == target {
+	    bail!("A trigger cannot toggle itself");
+	}
+	let i =
+	    match self.triggers.iter().position(|t| t.at == at) {
+		Some(i) => i,
+		None => {
+		    self.triggers.push(Trigger { at,targets:Vec::new(),active:false });
+		    self.triggers.len() - 1
+		}
+	    };
+	let trigger = &mut self.triggers[i];
+	if trigger.targets.iter().any(|st| st.at == target) {
+	    trigger.targets.retain(|st| st.at != target);
+	    if trigger.targets.is_empty() {
+		self.triggers.remove(i);
+	    }
+	    Ok(false)
+	} else {
+	    trigger.targets.push(SwitchTarget { at:target,closed });
+	    Ok(true)
+	}
+    }
+
+    pub fn sign_at(&self,at:&TileAddress)->Option<&Dialogue> {
+	self.signs.iter().find(|s| s.at == *at).map(|s| &s.dialogue)
+    }
+
+    /// Set the text of the sign at a tile, removing it if empty
+    pub fn set_sign(&mut self,at:TileAddress,dialogue:Dialogue)->Result<()> {
+	if self.get_tile(&at) != Some(Tile::Sign) {
+	    bail!("No sign in room {} at ({},{})",at.room_id,at.iy,at.ix);
+	}
+	self.signs.retain(|s| s.at != at);
+	if !dialogue.is_empty() {
+	    self.signs.push(Sign { at,dialogue });
+	}
+	Ok(())
+    }
+
+    /// The index of the creature at a tile, if any
+    pub fn creature_at(&self,ta:&TileAddress)->Option<usize> {
+	self.creatures.iter()
+	    .position(|c| c.room == ta.room_id && c.tile() == (ta.iy,ta.ix))
+    }
+
+    pub fn trigger_at(&self,at:&TileAddress)->Option<&Trigger> {
+	self.triggers.iter().find(|t| t.at == *at)
+    }
+
+    /// Is a tile toggled by some trigger?
+    pub fn is_switched(&self,ta:&TileAddress)->bool {
+	self.triggers.iter().any(|t| t.targets.iter().any(|st| st.at == *ta))
+    }
+
+    /// Can the trigger at `at` toggle all of its targets?  A wall
+    /// cannot close on the tile `occupied`, on a creature, or over an
+    /// object or a crate.
+    pub fn can_fire(&self,at:&TileAddress,occupied:Option<TileAddress>)->bool {
+	match self.trigger_at(at) {
+	    None => true,
+	    Some(t) => t.targets.iter()
+		.all(|st| match self.get_tile(&st.at) {
+		    Some(Tile::Empty) => occupied != Some(st.at) && self.creature_at(&st.at).is_none(),
+		    Some(tile @ (Tile::Object(_) | Tile::Crate { .. })) => tile == st.closed,
+		    _ => true
+		})
+	}
+    }
+
+    /// Toggle the targets of the trigger at `at`, except for walls
+    /// that would close over `occupied`
+    pub fn fire_trigger(&mut self,at:&TileAddress,occupied:Option<TileAddress>) {
+	let targets =
+	    match self.trigger_at(at) {
+		None => return,
+		Some(t) => t.targets.clone()
+	    };
+	for st in targets {
+	    match self.get_tile(&st.at) {
+		Some(Tile::Door(mut d)) => {
+		    d.locked = !d.locked;
+		    self.set_tile(&st.at,Tile::Door(d));
+		},
+		Some(Tile::Empty) if occupied != Some(st.at) && self.creature_at(&st.at).is_none() =>
+		    self.set_tile(&st.at,st.closed),
+		Some(t) if t == st.closed => self.set_tile(&st.at,Tile::Empty),
+		_ => ()
+	    }
+	}
+    }
+
+    /// Fire the triggers of pressure plates whose state has changed,
+    /// a plate being pressed by a crate or the hero standing at `hero`.
+    /// A trigger that cannot fire yet is left for a later call.
+    /// Returns true if any did.
+    pub fn update_plates(&mut self,hero:Option<TileAddress>)->bool {
+	let mut changed = Vec::new();
+	for i in 0..self.triggers.len() {
+	    let at = self.triggers[i].at;
+	    let pressed =
+		match self.get_tile(&at) {
+		    Some(Tile::Crate { on_plate:true }) => true,
+		    Some(Tile::Plate) => hero == Some(at),
+		    _ => continue
+		};
+	    if pressed != self.triggers[i].active && self.can_fire(&at,hero) {
+		self.triggers[i].active = pressed;
+		changed.push(at);
+	    }
+	}
+	for at in changed.iter() {
+	    self.fire_trigger(at,hero);
+	}
+	!changed.is_empty()
+    }
+
+    /// Number of tiles satisfying `f`, in all rooms
+    pub fn count_tiles<F:Fn(Tile)->bool>(&self,f:F)->usize {
+	self.rooms.values()
+	    .map(|room_ptr| {
+		room_ptr.yank().map.as_slice().iter()
+		    .filter(|&&t| f(t))
+		    .count()
+	    })
+	    .sum()
+    }
+
+    /// Number of tiles holding a given object, in all rooms
+    pub fn count_objects(&self,obj:Object)->usize {
+	self.count_tiles(|t| t == Tile::Object(obj))
+    }
+
+    /// Number of coins lying in the world
+    pub fn count_coins(&self)->usize {
+	self.count_tiles(|t| matches!(t,Tile::Object(o) if self.object_kind(o).coin))
+    }
+
+    /// The declared type of an object
+    pub fn object_type(&self,obj:Object)->Option<&ObjectType> {
+	self.objects.iter().find(|ot| ot.code == obj.0)
+    }
+
+    /// The properties of an object, made up if it is not declared
+    pub fn object_kind(&self,obj:Object)->ObjectType {
+	self.object_type(obj).cloned().unwrap_or_else(|| ObjectType::unknown(obj))
+    }
+
+    /// Declare an object type, replacing any with the same code
+    pub fn declare(&mut self,ot:ObjectType) {
+	match self.objects.iter_mut().find(|o| o.code == ot.code) {
+	    Some(o) => *o = ot,
+	    None => self.objects.push(ot)
+	}
+    }
+
+    /// The declared type of a custom tile
+    pub fn tile_type(&self,code:char)->Option<&TileType> {
+	self.tile_types.iter().find(|tt| tt.code == code)
+    }
+
+    /// The properties of a custom tile, made up if it is not declared
+    pub fn tile_kind(&self,code:char)->TileType {
+	self.tile_type(code).cloned().unwrap_or_else(|| TileType::unknown(code))
+    }
+
+    /// Declare a tile type, replacing any with the same code
+    pub fn declare_tile(&mut self,tt:TileType) {
+	match self.tile_types.iter_mut().find(|t| t.code == tt.code) {
+	    Some(t) => *t = tt,
+	    None => self.tile_types.push(tt)
+	}
+    }
+
+    /// Number of tiles of a declared type, in all rooms
+    pub fn count_custom(&self,code:char)->usize {
+	self.count_tiles(|t| matches!(t,Tile::Custom { code:c,.. } if c == code))
+    }
+
+    /// Restart the animation of the tiles of a type, after its frames
+    /// have changed
+    pub fn reset_custom(&self,code:char) {
+	let tile = self.tile_kind(code).tile();
+	for room_ptr in self.rooms.values() {
+	    let mut rm = room_ptr.yank_mut();
+	    for iy in 0..rm.rows {
+		for ix in 0..rm.cols {
+		    if matches!(rm.map[[iy,ix]],Tile::Custom { code:c,.. } if c == code) {
+			rm.map[[iy,ix]] = tile;
+		    }
+		}
+	    }
+	}
+    }
+
     pub fn set_tile(&self,ta:&TileAddress,tile:Tile) {
 	self.rooms.get(&ta.room_id)
 	    .map_or((),|room_ptr| {
@@ -204,3 +1082,172 @@ impl World {
     }
 }
 
+
+#[test]
+fn test_update_door() {
+    let mut world = World::new();
+    world.add_room(1,"ONE",&["#0#1#"]).unwrap();
+    world.add_room(2,"TWO",&["#0#"]).unwrap();
+    world.connect(1,0,2,0);
+
+    let mut d = world.get_door(1,0).unwrap();
+    d.id = 5;
+    world.update_door(1,0,d).unwrap();
+    assert!(world.get_door(1,0).is_none());
+    assert_eq!(world.get_door(2,0).unwrap().target,Some(Target { room:1,door:5 }));
+
+    let mut d = world.get_door(1,1).unwrap();
+    d.target = Some(Target { room:2,door:0 });
+    assert!(world.update_door(1,1,d).is_err());
+
+    let mut d = world.get_door(1,5).unwrap();
+    d.target = Some(Target { room:1,door:1 });
+    world.update_door(1,5,d).unwrap();
+    assert_eq!(world.get_door(2,0).unwrap().target,None);
+    assert_eq!(world.get_door(1,1).unwrap().target,Some(Target { room:1,door:5 }));
+}
+
+#[test]
+fn test_reconnect() {
+    let mut world = World::new();
+    world.add_room(1,"ONE",&["#0#1#"]).unwrap();
+    world.add_room(2,"TWO",&["#0#1#"]).unwrap();
+    world.connect(1,0,2,0);
+    world.connect(1,1,2,1);
+
+    world.reconnect(1,0,2,1).unwrap();
+    assert_eq!(world.get_door(1,0).unwrap().target,Some(Target { room:2,door:1 }));
+    assert_eq!(world.get_door(2,1).unwrap().target,Some(Target { room:1,door:0 }));
+    assert_eq!(world.get_door(2,0).unwrap().target,None);
+    assert_eq!(world.get_door(1,1).unwrap().target,None);
+
+    assert_eq!(world.disconnect(2,1).unwrap(),Some(Target { room:1,door:0 }));
+    assert_eq!(world.get_door(1,0).unwrap().target,None);
+    assert!(world.disconnect(2,7).is_err());
+}
+
+#[test]
+fn test_delete_room() {
+    let mut world = World::new();
+    world.add_room(1,"ONE",&["#0#1#"]).unwrap();
+    world.add_room(2,"TWO",&["#0H1#2#"]).unwrap();
+    world.add_room(3,"THREE",&["#0#"]).unwrap();
+    world.connect(1,0,2,0);
+    world.connect(1,1,2,1);
+    world.connect(2,2,3,0);
+    world.start = Some(TileAddress { room_id:2,iy:0,ix:2 });
+    world.goals = vec![Goal::ReachRainbow,
+		       Goal::ReachRoom { room:2,within:None },
+		       Goal::Deliver { object:Object::KEY,to:TileAddress { room_id:2,iy:0,ix:2 } },
+		       Goal::ReachRoom { room:3,within:None }];
+
+    assert_eq!(world.delete_room(2,false),vec![(1,0),(1,1),(3,0)]);
+    assert_eq!(world.start,None);
+    assert_eq!(world.goals,vec![Goal::ReachRainbow,Goal::ReachRoom { room:3,within:None }]);
+    assert!(world.room(2).is_none());
+    assert_eq!(world.get_door(3,0).unwrap().target,Some(Target { room:2,door:2 }));
+
+    world.add_room(4,"FOUR",&["#0#"]).unwrap();
+    world.reconnect(3,0,4,0).unwrap();
+    assert_eq!(world.delete_room(4,true),vec![(3,0)]);
+    assert_eq!(world.get_door(3,0).unwrap().target,None);
+}
+
+#[test]
+fn test_renumber() {
+    let mut world = World::new();
+    world.add_room(100,"ONE",&["#0H1#"]).unwrap();
+    world.add_room(105,"TWO",&["#0#"]).unwrap();
+    world.connect(100,0,105,0);
+    world.start = Some(TileAddress { room_id:100,iy:0,ix:2 });
+
+    let copy = world.duplicate_room(100).unwrap();
+    assert_eq!(copy,106);
+    assert_eq!(world.get_door(106,0).unwrap().target,None);
+    assert_eq!(world.get_door(100,0).unwrap().target,Some(Target { room:105,door:0 }));
+    world.connect(106,1,100,1);
+
+    let remap = world.compact(1).unwrap();
+    assert_eq!(remap.get(&105),Some(&2));
+    assert_eq!(world.room_list(),vec![1,2,3]);
+    assert_eq!(world.get_room(3).yank().id,3);
+    assert_eq!(world.get_door(1,0).unwrap().target,Some(Target { room:2,door:0 }));
+    assert_eq!(world.get_door(2,0).unwrap().target,Some(Target { room:1,door:0 }));
+    assert_eq!(world.get_door(1,1).unwrap().target,Some(Target { room:3,door:1 }));
+    assert_eq!(world.start.map(|ta| ta.room_id),Some(1));
+
+    let remap : BTreeMap<usize,usize> = [(1,2)].into_iter().collect();
+    assert!(world.renumber(&remap).is_err());
+}
+
+#[test]
+fn test_load_text() {
+    use std::io::Cursor;
+    let text = "ROOM 1\nONE\n #0H#\n ##\nENDROOM\nROOM 2\nTWO\n #0#\nENDROOM\nCONN 1 0 2 0\nEND\n";
+    let world = World::load_text(&mut Cursor::new(text.replace("END\n","REQUIRE 2 0 TOLL 3\nEND\n"))).unwrap();
+    assert_eq!(world.rooms[&1].yank().dims(),(2,4));
+    assert_eq!(world.get_door(2,0).unwrap().requires,Some(Requirement::Coins { count:3,consume:true }));
+    assert_eq!(world.get_tile(&TileAddress { room_id:1,iy:1,ix:3 }),Some(Tile::Brick));
+    assert_eq!(world.get_door(2,0).unwrap().target,Some(Target { room:1,door:0 }));
+
+    let text = "ROOM 3\nTHREE\n !H\nENDROOM\nSIGN 3 0 0\n HELLO\n ---\n BYE\nENDSIGN\n\
+		SCRIPT (on:EnterRoom(3),actions:[Say(\"HI\")])\nEND\n";
+    let world = World::load_text(&mut Cursor::new(text)).unwrap();
+    assert_eq!(world.sign_at(&TileAddress { room_id:3,iy:0,ix:0 }).unwrap().pages.len(),2);
+    assert_eq!(world.scripts.len(),1);
+
+    let text = "ROOM 4\nFOUR\n gzH\nENDROOM\nOBJECT (code:'g',name:\"A GEM\",sprite:(2,0),consumable:true,score:10)\nEND\n";
+    let mut world = World::load_text(&mut Cursor::new(text)).unwrap();
+    assert_eq!(world.object_type(Object('g')).unwrap().score,10);
+    assert!(world.object_type(Object::KEY).is_some());
+    assert_eq!(world.validate().unwrap_err().to_string(),"Room 4: undeclared object 'z' at (0,1)");
+    world.declare(ObjectType::new('z',"A ZIP",(0,6)));
+    world.creatures.push(Creature::new("BAT",4,(9,9)));
+    assert_eq!(world.validate().unwrap_err().to_string(),"Creature \"BAT\": (9,9) is outside room 4");
+    world.creatures[0].room = 5;
+    assert_eq!(world.validate().unwrap_err().to_string(),"Creature \"BAT\" is in missing room 5");
+
+    let text = "TILE (code:':',name:\"LAVA\",sprite:(4,4),frames:3,period:2,passable:true,damage:2)\n\
+		ROOM 5\nFIVE\n :H\nENDROOM\nEND\n";
+    let mut world = World::load_text(&mut Cursor::new(text)).unwrap();
+    world.validate().unwrap();
+    let lava = world.tile_kind(':');
+    assert_eq!(world.get_tile(&TileAddress { room_id:5,iy:0,ix:0 }),Some(lava.tile()));
+    assert_eq!(lava.sprite_at(Periodic { i:2,m:3,j:0,n:2 }),(6,4));
+    world.inventory_capacity = 10;
+    assert_eq!(world.validate().unwrap_err().to_string(),"The inventory capacity must be between 1 and 9");
+    world.inventory_capacity = 9;
+    world.hazards.enabled = true;
+    world.hazards.health = 0;
+    assert_eq!(world.validate().unwrap_err().to_string(),"The hero must start with some health");
+    world.hazards.health = 3;
+    world.tile_types.clear();
+    assert_eq!(world.validate().unwrap_err().to_string(),"Room 5: undeclared tile ':' at (0,0)");
+
+    for (text,msg) in [
+	("ROOM 1\nONE\n #?#\nENDROOM\nEND\n","Room 1 at line 1: Unsupported character '?' at row 0 column 1"),
+	("ROOM 1\nONE\nENDROOM\nEND\n","Room 1 at line 1: Empty room"),
+	("ROOM 1\nONE\n #0#\n","Unexpected end of file at line 4"),
+	("ROOM 1\nONE\n #0#\nENDROOM\nCONN 1 0 2 0\nEND\n","No door 0 in room 2 at line 5"),
+	("LOCK 1 0 K\nEND\n","Cannot lock missing door 0 of room 1 at line 1"),
+	("SIGN 1 0 0\n HI\nENDSIGN\nEND\n","No sign in room 1 at (0,0) at line 1"),
+	("SCRIPT\nEND\n","Missing argument at line 1"),
+	("CREATURE\nEND\n","Missing argument at line 1"),
+	("OBJECT\nEND\n","Missing argument at line 1"),
+	("TILE\nEND\n","Missing argument at line 1"),
+	("OBJECT (code:'R',name:\"A ROCK\",sprite:(0,0))\nEND\n","Object code 'R' is already taken at line 1"),
+	("TILE (code:'#',name:\"WALL\",sprite:(0,0))\nEND\n","Tile code '#' is already taken at line 1")
+    ] {
+	let e = World::load_text(&mut Cursor::new(text)).unwrap_err();
+	assert_eq!(e.to_string(),msg);
+    }
+}
+
+#[test]
+fn test_load_shipped_worlds() {
+    for path in ["../worlds/first.wld","../worlds/first.ron"] {
+	let mut world = World::new();
+	world.load(path).unwrap();
+	assert!(!world.rooms.is_empty());
+    }
+}
@@ -1,12 +1,12 @@
 pub use std::env;
 pub use std::error::Error;
 pub use std::path::Path;
-pub use std::time::Duration;
+pub use std::time::{Duration,Instant};
 pub use std::collections::BTreeMap;
 pub use std::sync::Mutex;
 pub use sdl2::pixels::Color;
 pub use sdl2::event::Event;
-pub use sdl2::keyboard::Keycode;
+pub use sdl2::keyboard::{Keycode,Mod};
 pub use sdl2::render::{Canvas,Texture,TextureCreator,TextureQuery,RenderTarget};
 pub use sdl2::rect::Rect;
 pub use sdl2::surface::Surface;
@@ -15,17 +15,20 @@ pub use sdl2::audio::AudioSpecDesired;
 pub use sdl2::rect::Point;
 
 pub use mzg_game::*;
-pub use object::Object;
 pub use world::World;
 pub use tiles::*;
 pub use room::Room;
 pub use ptr::*;
 pub use mini_rng::MiniRNG;
+pub use facing::Facing;
+pub use position::Position;
+pub use hero::Hero;
+pub use goal::Status;
+pub use sounds::Sounds;
+pub use text::Catalog;
 
 pub use crate::{
-    facing::Facing,
-    position::Position,
+    listener::Listener,
     synthesizer::Synthesizer,
-    hero::Hero,
-    sounds::Sounds
+    watcher::Watcher
 };
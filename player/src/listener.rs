@@ -0,0 +1,68 @@
+use std::{
+    io::{
+	ErrorKind,
+	Read
+    },
+    net::{
+	TcpListener,
+	TcpStream
+    }
+};
+
+use anyhow::Result;
+
+use mzg_game::link::Command;
+
+struct Client {
+    stream:TcpStream,
+    buf:Vec<u8>
+}
+
+/// Accepts connections from the editor and collects the commands it
+/// sends, without ever blocking
+pub struct Listener {
+    listener:TcpListener,
+    clients:Vec<Client>
+}
+
+impl Listener {
+    pub fn new(address:&str)->Result<Self> {
+	let listener = TcpListener::bind(address)?;
+	listener.set_nonblocking(true)?;
+	Ok(Self {
+	    listener,
+	    clients:Vec::new()
+	})
+    }
+
+    /// Returns the commands received since the last call
+    pub fn poll(&mut self)->Vec<Result<Command>> {
+	while let Ok((stream,_)) = self.listener.accept() {
+	    if stream.set_nonblocking(true).is_ok() {
+		self.clients.push(Client { stream,buf:Vec::new() });
+	    }
+	}
+
+	let mut commands = Vec::new();
+	self.clients.retain_mut(|client| {
+	    let mut chunk = [0;4096];
+	    let alive =
+		loop {
+		    match client.stream.read(&mut chunk) {
+			Ok(0) => break false,
+			Ok(n) => client.buf.extend_from_slice(&chunk[0..n]),
+			Err(e) if e.kind() == ErrorKind::WouldBlock => break true,
+			Err(e) if e.kind() == ErrorKind::Interrupted => (),
+			Err(_) => break false
+		    }
+		};
+	    while let Some(i) = client.buf.iter().position(|&c| c == b'\n') {
+		let line : Vec<u8> = client.buf.drain(0..=i).collect();
+		let line = String::from_utf8_lossy(&line);
+		commands.push(Command::parse(line.trim_end()));
+	    }
+	    alive
+	});
+	commands
+    }
+}
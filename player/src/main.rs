@@ -1,14 +1,54 @@
 #![allow(dead_code)]
 
 mod common;
-mod facing;
-mod hero;
-mod position;
-mod sounds;
+mod listener;
 mod synthesizer;
+mod watcher;
 
 use common::*;
 
+/// Split a line of text into lines of at most `columns` characters,
+/// between words where possible
+fn wrap(text:&str,columns:usize)->Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+	let mut word : Vec<char> = word.chars().collect();
+	while word.len() > columns {
+	    if !line.is_empty() {
+		lines.push(std::mem::take(&mut line));
+	    }
+	    lines.push(word.drain(..columns).collect());
+	}
+	let n = line.chars().count();
+	if n > 0 && n + 1 + word.len() > columns {
+	    lines.push(std::mem::take(&mut line));
+	}
+	if !line.is_empty() {
+	    line.push(' ');
+	}
+	line.extend(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+	lines.push(line);
+    }
+    lines
+}
+
+/// Replace the world by a new version, keeping the hero where it is if
+/// possible.  On error, the world and the hero are left untouched.
+fn install(new_world:World,world:&mut World,hero:&mut Hero)->anyhow::Result<()> {
+    hero.relocate(&new_world)?;
+    *world = new_world;
+    Ok(())
+}
+
+fn reload(world_path:&str,world:&mut World,hero:&mut Hero)->anyhow::Result<()> {
+    let mut new_world = World::new();
+    new_world.load(world_path)?;
+    install(new_world,world,hero)
+}
+
 pub fn main() -> Result<(),Box<dyn Error>> {
     let font_height : usize = 16;
     let font_width : usize = 10; // XXX
@@ -17,6 +57,11 @@ pub fn main() -> Result<(),Box<dyn Error>> {
     let font_path : &Path = Path::new(&args[1]);
     let tiles_path = &args[2];
     let world_path = &args[3];
+    let catalog =
+	match env::var("MZG_LOCALE") {
+	    Ok(path) => Catalog::load(&path).map_err(|e| format!("Cannot load locale {}: {}",path,e))?,
+	    Err(_) => Catalog::english()
+	};
     let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
     let font = ttf_context.load_font(font_path, font_height as u16)?;
     // font.set_style(sdl2::ttf::FontStyle::BOLD);
@@ -43,6 +88,12 @@ pub fn main() -> Result<(),Box<dyn Error>> {
     sounds.insert(Sounds::EatFood,synth.generate(500.0,400.0,0.200, 0.1,0.3));
     sounds.insert(Sounds::GoThroughDoor,synth.generate(400.0,200.0,0.500, 0.2,1.0));
     sounds.insert(Sounds::BlockedAgainstWall,synth.generate(175.0,150.0,0.100, 0.2,0.2));
+    sounds.insert(Sounds::Teleport,synth.generate(200.0,1600.0,0.400, 0.2,0.8));
+    sounds.insert(Sounds::Hurt,synth.generate(300.0,150.0,0.150, 0.1,0.5));
+    sounds.insert(Sounds::Die,synth.generate(600.0,50.0,1.000, 0.1,0.9));
+    sounds.insert(Sounds::Splash,synth.generate(800.0,700.0,0.100, 0.5,0.5));
+    sounds.insert(Sounds::Push,synth.generate(120.0,90.0,0.150, 0.5,0.5));
+    sounds.insert(Sounds::Switch,synth.generate(1500.0,1500.0,0.030, 0.1,0.5));
 
     device.resume();
 
@@ -73,6 +124,18 @@ pub fn main() -> Result<(),Box<dyn Error>> {
     let mut world = World::new();
     world.load(world_path)?;
     let mut hero = Hero::new(&world,"FELIX");
+    let mut watcher = Watcher::new(world_path,Duration::from_millis(500));
+    let mut load_error : Option<String> = None;
+    // Number of messages scrolled back in the history, when it is shown
+    let mut history : Option<usize> = None;
+    let mut listener =
+	match Listener::new(&link::address()) {
+	    Ok(listener) => Some(listener),
+	    Err(e) => {
+		println!("Not listening for the editor: {}",e);
+		None
+	    }
+	};
     // let mut walk = Walk::new();
 
     let clear = |canvas:&mut Canvas<_>|->Result<(),String> {
@@ -86,8 +149,9 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 	my:usize,
 	bw:usize,
 	bh:usize,
-	ox:usize,
-	oy:usize,
+	/// Right end and top of the inventory bar
+	ix:usize,
+	iy:usize,
 	rainbow:Vec<sdl2::pixels::Color>,
 	rainbow_index:usize,
 	texture:Texture<'a>,
@@ -95,10 +159,12 @@ pub fn main() -> Result<(),Box<dyn Error>> {
     }
 
     const BW : usize = 32;
+    /// Messages shown at once in the history
+    const HISTORY_LINES : usize = 20;
     const BH : usize = 32;
 
     impl<'a> Redrawer<'a> {
-	fn new<T>(mx:usize,my:usize,ox:usize,oy:usize,tiles:&str,texture_creator:&'a TextureCreator<T>)->Self {
+	fn new<T>(mx:usize,my:usize,ix:usize,iy:usize,tiles:&str,texture_creator:&'a TextureCreator<T>)->Self {
 	    let tiles = Surface::from_file(tiles).unwrap();
 	    let texture = texture_creator.create_texture_from_surface(&tiles).unwrap();
 	    let rainbow = vec![
@@ -114,8 +180,8 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 		my,
 		bw:BW,
 		bh:BH,
-		ox,
-		oy,
+		ix,
+		iy,
 		rainbow_index:0,
 		rainbow,
 		texture,
@@ -131,7 +197,8 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 	    Color::RGB(r as u8,g as u8,b as u8)
 	}
 
-	fn redraw<T:RenderTarget>(&mut self,canvas:&mut Canvas<T>,rm:&Room,hero:&Hero)->Result<(),String> {
+	fn redraw<T:RenderTarget>(&mut self,canvas:&mut Canvas<T>,rm:&Room,world:&World,
+				  hero:&Hero)->Result<(),String> {
 	    self.rainbow_index += 1;
 	    if self.rainbow_index == self.rainbow.len() {
 		self.rainbow_index = 0;
@@ -156,6 +223,30 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 					th as u32);
 		    canvas.copy(&self.texture,Some(src),Some(dst)).unwrap();
 		};
+		let framed = |col,edge| {
+		    let mut canvas = canvas.lock().unwrap();
+		    canvas.set_draw_color(edge);
+		    canvas.fill_rect(dst).unwrap();
+		    canvas.set_draw_color(col);
+		    let w = (self.bw/8) as i32;
+		    canvas.fill_rect(Rect::new(dst.x() + w,dst.y() + w,
+					       dst.width() - 2*w as u32,dst.height() - 2*w as u32)).unwrap();
+		};
+		let arrow = |col,pointer,f:Facing| {
+		    let mut canvas = canvas.lock().unwrap();
+		    canvas.set_draw_color(col);
+		    canvas.fill_rect(dst).unwrap();
+		    canvas.set_draw_color(pointer);
+		    let (di,dj) = f.to_deltas();
+		    let n = (self.bw.min(self.bh)/3) as i32;
+		    let c = dst.center();
+		    for k in -n..=n {
+			let w = (n - k)/2;
+			let (x,y) = (c.x() + k*dj as i32,c.y() + k*di as i32);
+			canvas.draw_line(Point::new(x - w*di as i32,y - w*dj as i32),
+					 Point::new(x + w*di as i32,y + w*dj as i32)).unwrap();
+		    }
+		};
 		fill(Color::RGB(255,255,0));
 		match tl {
 		    Tile::Fire(p) => tile(4+p.i,4),
@@ -168,16 +259,13 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 		    Tile::MetalRamp(Corner::SW) => tile(1,12),
 		    Tile::MetalRamp(Corner::SE) => tile(1,13),
 		    Tile::MetalFoot => tile(3,12),
-		    Tile::Object(o) => match o {
-			Object::Coin => tile(0,1),
-			Object::IceCream => tile(0,3),
-			Object::Key => tile(0,4),
-			Object::ToyCar => tile(0,5),
-			Object::SquaresAndTriangles => tile(0,6),
-			Object::Tomato => tile(1,5),
-			Object::Eggplant => tile(1,6),
-			Object::Banana => tile(1,7),
-			Object::Carrot => tile(1,8)
+		    Tile::Custom { code,phase } => {
+			let (u,v) = world.tile_kind(code).sprite_at(phase);
+			tile(u as u16,v as u16)
+		    },
+		    Tile::Object(o) => {
+			let (u,v) = world.object_kind(o).sprite;
+			tile(u as u16,v as u16)
 		    },
 		    Tile::Door(Door{ key:None, .. }) => tile(0,2),
 		    Tile::Door(Door{ key:Some(_), locked:true, ..}) => tile(1,3),
@@ -190,6 +278,17 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 		    Tile::Water(p) => tile(p.i,9),
 		    Tile::Empty => fill(Color::RGB(  0,  0,  0)),
 		    Tile::Rainbow => fill(self.rainbow[self.rainbow_index]),
+		    Tile::Crate { on_plate:false } => framed(Color::RGB(139, 90, 43),Color::RGB(205,133, 63)),
+		    Tile::Crate { on_plate:true } => framed(Color::RGB(139, 90, 43),Color::RGB(160,160,160)),
+		    Tile::Plate => framed(Color::RGB(  0,  0,  0),Color::RGB(160,160,160)),
+		    Tile::Lever { on:false } => framed(Color::RGB(160,160,160),Color::RGB(200, 40, 40)),
+		    Tile::Lever { on:true } => framed(Color::RGB(160,160,160),Color::RGB( 40,200, 40)),
+		    Tile::CoinSlot { paid:false } => framed(Color::RGB(  0,  0,  0),Color::RGB(255,215,  0)),
+		    Tile::CoinSlot { paid:true } => framed(Color::RGB(255,215,  0),Color::RGB(255,215,  0)),
+		    Tile::Sign => framed(Color::RGB(240,230,200),Color::RGB(139, 90, 43)),
+		    Tile::Ice => fill(Color::RGB(180,220,255)),
+		    Tile::Conveyor(f) => arrow(Color::RGB( 80, 80, 80),Color::RGB(255,215,  0),f),
+		    Tile::OneWay(f) => arrow(Color::RGB(  0,  0,  0),Color::RGB(255,255,255),f),
 		}
 	    };
 	    
@@ -201,10 +300,33 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 		}
 	    }
 
+	    for creature in world.creatures.iter().filter(|c| c.room == rm.id) {
+		let (y,x) = creature.position().coords();
+		let dst = Rect::new((self.mx as f32 + x*self.bw as f32) as i32,
+				    (self.my as f32 + y*self.bh as f32) as i32,
+				    self.bw as u32,
+				    self.bh as u32);
+		let src = Rect::new(14*16,0,16,16);
+		canvas.lock().unwrap().copy(&self.texture,Some(src),Some(dst))?;
+	    }
+
 	    {
-		match &hero.carrying() {
-		    None => (),
-		    Some(o) => draw(self.ox,self.oy,Tile::Object(*o))
+		let inventory = hero.inventory();
+		let n = inventory.capacity();
+		for i in 0..n {
+		    let x = self.ix - (n - i)*self.bw;
+		    match inventory.items().get(i) {
+			None => draw(x,self.iy,Tile::Empty),
+			Some(&o) => draw(x,self.iy,Tile::Object(o))
+		    }
+		    let mut canvas = canvas.lock().unwrap();
+		    canvas.set_draw_color(
+			if i == inventory.selected_index() && !inventory.is_empty() {
+			    Color::RGB(255,255,255)
+			} else {
+			    Color::RGB( 64, 64, 64)
+			});
+		    canvas.draw_rect(Rect::new(x as i32,self.iy as i32,self.bw as u32,self.bh as u32))?;
 		}
 	    }
 
@@ -256,7 +378,7 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 	}
     }
 
-    let mut redrawer = Redrawer::new(mx,my,width as usize-BW,height as usize-font_height-8,
+    let mut redrawer = Redrawer::new(mx,my,width as usize,height as usize-font_height-8-BH-4,
 				     tiles_path,&texture_creator);
 
     let write = |canvas:&mut Canvas<_>,x,y,text:&str,color| {
@@ -273,23 +395,61 @@ pub fn main() -> Result<(),Box<dyn Error>> {
     };
 
     // println!("WORLD: {:?}",world);
+    let frame_period = Duration::from_secs(1)/goal::TICKS_PER_SECOND as u32;
     'running: loop {
+	let frame_start = Instant::now();
 	clear(&mut canvas)?;
 	if hero.won() {
-	    write(&mut canvas,0,0,"YOU WON THE GAME !!!",redrawer.random_color());
+	    write(&mut canvas,0,0,catalog.get("hud.won"),redrawer.random_color());
+	} else if hero.dead() {
+	    write(&mut canvas,0,0,catalog.get("hud.game_over"),Color::RGB(255,  0,  0));
 	} else {
 	    write(&mut canvas,0,0,
 		  &world.get_room(hero.room()).yank().name,Color::RGB(255,  0,  0));
 	}
-	write(&mut canvas,0,height-font_height as u32-8,
-	      hero.message(),
-	      Color::RGB(255,255,255));
+	let mut x = width/2;
+	for goal in world.active_goals() {
+	    let (mark,color) =
+		match hero.goal_status(&world,goal) {
+		    Status::Pending => (' ',Color::RGB(255,255,255)),
+		    Status::Done => ('X',Color::RGB(  0,255,  0)),
+		    Status::Failed => ('!',Color::RGB(255,  0,  0))
+		};
+	    let text = format!("[{}] {}",mark,catalog.render(&hero.objective(&world,goal)));
+	    x += write(&mut canvas,x,0,&text,color) + font_width as u32*2;
+	}
+	match &load_error {
+	    None => write(&mut canvas,0,height-font_height as u32-8,
+			  &hero.log().back().map(|m| catalog.render(m)).unwrap_or_default(),
+			  Color::RGB(255,255,255)),
+	    Some(e) => write(&mut canvas,0,height-font_height as u32-8,
+			     e,
+			     Color::RGB(255,  0,  0))
+	};
+
+	let mut reload_requested = watcher.changed();
+
 	write(&mut canvas,width-font_width as u32*24,height-font_height as u32-8,
-	      &format!("FOODS {:5}",hero.foods()),
+	      &format!("{} {:5}",catalog.get("hud.foods"),hero.foods()),
 	      Color::RGB(  0,255,0));
 	write(&mut canvas,width-font_width as u32*12,height-font_height as u32-8,
-	      &format!("COINS {:5}",hero.coins()),
+	      &format!("{} {:5}",catalog.get("hud.coins"),hero.coins()),
 	      Color::RGB(255,255,0));
+	if world.objects.iter().any(|ot| ot.score > 0) {
+	    write(&mut canvas,width-font_width as u32*62,height-font_height as u32-8,
+		  &format!("{} {:5}",catalog.get("hud.score"),hero.score()),
+		  Color::RGB(128,200,255));
+	}
+	if world.hazards.enabled {
+	    let lives =
+		match hero.lives() {
+		    None => String::new(),
+		    Some(n) => format!("{} {:2}  ",catalog.get("hud.lives"),n)
+		};
+	    write(&mut canvas,width-font_width as u32*48,height-font_height as u32-8,
+		  &format!("{}{} {:2}/{:2}",lives,catalog.get("hud.health"),hero.health(),world.hazards.health),
+		  Color::RGB(255,128,128));
+	}
 
 	loop {
 	    match hero.next_sound() {
@@ -328,20 +488,27 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 			_ => (),
 		    }
 		},
-		Event::KeyDown { keycode: Some(kc), repeat, .. } => {
+		Event::KeyDown { keycode: Some(kc), keymod, repeat, .. } => {
 		    match kc {
 			Keycode::Escape => {
 			    println!("BYE");
 			    break 'running
 			},
-			Keycode::F5 => {
-			    println!("Reloading...");
-			    world.clear();
-			    world.load(world_path)?;
+			Keycode::F5 => reload_requested = true,
+			Keycode::L if !repeat =>
+			    history = if history.is_some() { None } else { Some(0) },
+			Keycode::PageUp => {
+			    let n = hero.log().len().saturating_sub(HISTORY_LINES);
+			    history = Some(history.map_or(0,|k| (k + HISTORY_LINES/2).min(n)));
 			},
+			Keycode::PageDown =>
+			    history = history.map(|k| k.saturating_sub(HISTORY_LINES/2)),
 			_ => {
 			    if !repeat {
 				match kc {
+				    Keycode::Up if hero.talking() => hero.choose(-1),
+				    Keycode::Down if hero.talking() => hero.choose(1),
+				    Keycode::Space | Keycode::Return if hero.talking() => hero.advance(),
 				    Keycode::Left => {
 					hero.start(0,-1)
 				    },
@@ -349,6 +516,21 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 				    Keycode::Up => hero.start(-1,0),
 				    Keycode::Down => hero.start(1,0),
 				    Keycode::Space => hero.drop(&mut world),
+				    Keycode::Tab =>
+					if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+					    hero.cycle(&world,-1)
+					} else {
+					    hero.cycle(&world,1)
+					},
+				    Keycode::Num1 => hero.select(&world,0),
+				    Keycode::Num2 => hero.select(&world,1),
+				    Keycode::Num3 => hero.select(&world,2),
+				    Keycode::Num4 => hero.select(&world,3),
+				    Keycode::Num5 => hero.select(&world,4),
+				    Keycode::Num6 => hero.select(&world,5),
+				    Keycode::Num7 => hero.select(&world,6),
+				    Keycode::Num8 => hero.select(&world,7),
+				    Keycode::Num9 => hero.select(&world,8),
 				    _ => (),
 				}
 			    }
@@ -364,6 +546,28 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 	    }
 	}
 
+	if reload_requested {
+	    println!("Reloading...");
+	    watcher.reset();
+	    load_error =
+		match reload(world_path,&mut world,&mut hero) {
+		    Ok(()) => None,
+		    Err(e) => Some(format!("CANNOT RELOAD WORLD: {}",e))
+		};
+	}
+
+	if let Some(listener) = listener.as_mut() {
+	    for cmd in listener.poll() {
+		let res =
+		    match cmd {
+			Ok(link::Command::World(new_world)) => install(*new_world,&mut world,&mut hero),
+			Ok(link::Command::Teleport(ta)) => hero.teleport(&world,ta),
+			Err(e) => Err(e)
+		    };
+		load_error = res.err().map(|e| format!("EDITOR LINK: {}",e));
+	    }
+	}
+
 	hero.tick(&mut world);
 
 
@@ -373,10 +577,69 @@ pub fn main() -> Result<(),Box<dyn Error>> {
 	    room.next();
 	    redrawer.redraw(&mut canvas,
 			    &room,
+			    &world,
 			    &hero)?;
 	}
+
+	if let Some(conversation) = hero.conversation() {
+	    let page = conversation.page();
+	    let columns = ((width as usize).saturating_sub(48)/font_width).max(1);
+	    let line_height = font_height + 4;
+	    // Keep the box below the top line and the choices visible,
+	    // cutting the text if needed
+	    let max_lines = ((height as usize).saturating_sub(my + font_height + 8 + BH + 8 + 16)
+			     /line_height).max(1);
+	    let mut lines : Vec<(String,Color)> = page.text.lines()
+		.flat_map(|u| wrap(u,columns))
+		.map(|u| (u,Color::RGB(255,255,255)))
+		.collect();
+	    let room = max_lines.saturating_sub(page.choices.len());
+	    if lines.len() > room {
+		lines.truncate(room.saturating_sub(1));
+		lines.push(("...".to_string(),Color::RGB(255,255,255)));
+	    }
+	    for (i,choice) in page.choices.iter().enumerate() {
+		lines.push(
+		    if i == conversation.choice {
+			(format!("> {}",choice.text),Color::RGB(255,255,  0))
+		    } else {
+			(format!("  {}",choice.text),Color::RGB(160,160,160))
+		    });
+	    }
+	    lines.truncate(max_lines);
+	    let h = lines.len()*line_height + 16;
+	    let y0 = (height as usize).saturating_sub(font_height + 8 + BH + 8 + h);
+	    let frame = Rect::new(16,y0 as i32,width - 32,h as u32);
+	    canvas.set_draw_color(Color::RGB(  0,  0, 64));
+	    canvas.fill_rect(frame)?;
+	    canvas.set_draw_color(Color::RGB(255,255,255));
+	    canvas.draw_rect(frame)?;
+	    for (k,(line,color)) in lines.iter().enumerate() {
+		write(&mut canvas,24,(y0 + 8 + k*line_height) as u32,line,*color);
+	    }
+	}
+	if let Some(back) = history {
+	    let log = hero.log();
+	    let end = log.len() - back.min(log.len());
+	    let start = end.saturating_sub(HISTORY_LINES);
+	    let line_height = font_height + 4;
+	    let h = (HISTORY_LINES + 1)*line_height + 16;
+	    let y0 = my + 8;
+	    let frame = Rect::new(16,y0 as i32,width - 32,h as u32);
+	    canvas.set_draw_color(Color::RGB( 32, 32, 32));
+	    canvas.fill_rect(frame)?;
+	    canvas.set_draw_color(Color::RGB(255,255,255));
+	    canvas.draw_rect(frame)?;
+	    write(&mut canvas,24,(y0 + 8) as u32,catalog.get("hud.messages"),Color::RGB(255,255,  0));
+	    for (k,m) in log.range(start..end).enumerate() {
+		write(&mut canvas,24,(y0 + 8 + (k + 1)*line_height) as u32,
+		      &catalog.render(m),Color::RGB(255,255,255));
+	    }
+	}
 	canvas.present();
-	::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+	// Sleep for what is left of the frame, so that time limits are
+	// counted in seconds
+	::std::thread::sleep(frame_period.saturating_sub(frame_start.elapsed()));
     }
     Ok(())
 }
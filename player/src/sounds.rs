@@ -1,9 +0,0 @@
-#[derive(Copy,Clone,Debug,PartialEq,Eq,PartialOrd,Ord)]
-pub enum Sounds {
-    Walk,
-    BlockedAgainstWall,
-    PickUpObject,
-    GoThroughDoor,
-    EatFood,
-    PickUpCoin
-}
@@ -0,0 +1,63 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{
+	Duration,
+	Instant,
+	SystemTime
+    }
+};
+
+/// Polls the modification time of a file.  A change is only reported
+/// once the modification time has been stable for one polling period,
+/// so that we don't read a file that is still being written.
+pub struct Watcher {
+    path:PathBuf,
+    period:Duration,
+    last_check:Instant,
+    mtime:Option<SystemTime>,
+    pending:Option<SystemTime>
+}
+
+impl Watcher {
+    pub fn new<P:Into<PathBuf>>(path:P,period:Duration)->Self {
+	let path = path.into();
+	let mtime = Self::mtime_of(&path);
+	Self {
+	    path,
+	    period,
+	    last_check:Instant::now(),
+	    mtime,
+	    pending:None
+	}
+    }
+
+    fn mtime_of(path:&PathBuf)->Option<SystemTime> {
+	fs::metadata(path).and_then(|md| md.modified()).ok()
+    }
+
+    /// Forget about any change up to now
+    pub fn reset(&mut self) {
+	self.mtime = Self::mtime_of(&self.path);
+	self.pending = None;
+    }
+
+    pub fn changed(&mut self)->bool {
+	if self.last_check.elapsed() < self.period {
+	    return false;
+	}
+	self.last_check = Instant::now();
+	let mtime = Self::mtime_of(&self.path);
+	if mtime.is_none() || mtime == self.mtime {
+	    self.pending = None;
+	    false
+	} else if mtime == self.pending {
+	    self.mtime = mtime;
+	    self.pending = None;
+	    true
+	} else {
+	    self.pending = mtime;
+	    false
+	}
+    }
+}